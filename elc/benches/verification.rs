@@ -0,0 +1,168 @@
+//! Benchmarks of the verification paths that dominate the time in the enclave
+use besu_qbft_elc::client::BesuQBFTLightClient;
+use besu_qbft_elc::client_state::ClientState;
+use besu_qbft_elc::commitment::verify_signature;
+use besu_qbft_elc::consensus_state::{ConsensusState, ValidatorSet};
+use besu_qbft_elc::header::EthHeader;
+use besu_qbft_elc::test_utils::qbft::{generate_validators, QbftChainSimulator, SimulatedBlock};
+use besu_qbft_elc::test_utils::trie::MockStateTrie;
+use besu_qbft_elc::test_utils::MockContext;
+use besu_qbft_elc::types::{Address, H256, U256};
+use core::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use hex_literal::hex;
+use light_client::types::{ClientId, Height, Time};
+use light_client::LightClient;
+
+const IBC_STORE_ADDRESS: Address = hex!("702e40245797c5a2108a566b3ce2bf14bc6af841");
+const VALIDATOR_COUNTS: [usize; 4] = [4, 16, 64, 128];
+
+fn time(secs: u64) -> Time {
+    Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap()
+}
+
+fn client_id() -> ClientId {
+    "hb-qbft-0".parse().unwrap()
+}
+
+/// returns the state whose IBC store has `n` commitments
+fn state(n: usize) -> MockStateTrie {
+    let mut state = MockStateTrie::new();
+    for i in 0..n {
+        state.set_ibc_commitment(
+            IBC_STORE_ADDRESS,
+            &format!("commitments/{}", i),
+            &i.to_be_bytes(),
+        );
+    }
+    // other accounts to make the account proof realistic
+    for i in 0..1000u64 {
+        let mut address = [0u8; 20];
+        address[12..].copy_from_slice(&i.to_be_bytes());
+        state.account_mut(address).balance = U256::from(i);
+    }
+    state
+}
+
+/// returns the context that has a client trusting the block at height 100, and the block at height 101
+fn setup_update_client(validators: usize, state: &MockStateTrie) -> (MockContext, SimulatedBlock) {
+    let mut chain = QbftChainSimulator::new(generate_validators(validators), 99, 1000);
+    let trusted = chain.next_block(state.state_root());
+    let block = chain.next_block(state.state_root());
+
+    let client_state = ClientState {
+        chain_id: U256::from(2018u64),
+        ibc_store_address: IBC_STORE_ADDRESS,
+        latest_height: Height::new(0, 100),
+        trusting_period: Duration::from_secs(100),
+        max_clock_drift: Duration::from_secs(10),
+        ..Default::default()
+    };
+    let consensus_state = ConsensusState {
+        timestamp: time(trusted.timestamp),
+        root: state.storage_root(&IBC_STORE_ADDRESS),
+        validators: ValidatorSet::Addresses(trusted.validators),
+        processed_time: None,
+    };
+    let mut ctx = MockContext::new(time(block.timestamp));
+    ctx.apply_create_client(
+        &client_id(),
+        client_state.into(),
+        consensus_state.try_into().unwrap(),
+        Height::new(0, 100),
+    );
+    (ctx, block)
+}
+
+fn bench_header_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("header_parsing");
+    for n in VALIDATOR_COUNTS {
+        let chain = QbftChainSimulator::new(generate_validators(n), 100, 1000);
+        let block = chain.build_block(101, 1001, H256::ZERO);
+        let sealed_header_rlp = block.sealed_header_rlp();
+        group.bench_with_input(BenchmarkId::new("parse", n), &block.header_rlp, |b, bz| {
+            b.iter(|| EthHeader::parse(black_box(bz)).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("parse_sealed", n),
+            &sealed_header_rlp,
+            |b, bz| b.iter(|| EthHeader::parse(black_box(bz)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_verify_signature(c: &mut Criterion) {
+    let chain = QbftChainSimulator::new(generate_validators(1), 100, 1000);
+    let block = chain.build_block(101, 1001, H256::ZERO);
+    c.bench_function("verify_signature", |b| {
+        b.iter(|| {
+            verify_signature(black_box(block.commit_hash), black_box(&block.seals[0])).unwrap()
+        })
+    });
+}
+
+fn bench_update_client(c: &mut Criterion) {
+    let state = state(16);
+    let account_proof = state.account_proof(&IBC_STORE_ADDRESS);
+    let mut group = c.benchmark_group("update_client");
+    group.sample_size(10);
+    for n in VALIDATOR_COUNTS {
+        let (ctx, block) = setup_update_client(n, &state);
+        let header = block.to_header(Height::new(0, 100), account_proof.clone());
+        group.bench_with_input(BenchmarkId::from_parameter(n), &header, |b, header| {
+            b.iter(|| {
+                BesuQBFTLightClient
+                    .update_client(&ctx, client_id(), header.clone().into())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_account_proof(c: &mut Criterion) {
+    let state = state(16);
+    let state_root = state.state_root();
+    let account_proof = state.account_proof(&IBC_STORE_ADDRESS);
+    let client_state = ClientState::default();
+    c.bench_function("verify_account_storage", |b| {
+        b.iter(|| {
+            client_state
+                .verify_account_storage(account_proof.clone(), state_root, &IBC_STORE_ADDRESS)
+                .unwrap()
+        })
+    });
+}
+
+fn bench_verify_memberships(c: &mut Criterion) {
+    let state = state(1024);
+    let root = state.storage_root(&IBC_STORE_ADDRESS);
+    let client_state = ClientState::default();
+    let mut group = c.benchmark_group("verify_memberships");
+    for n in [1usize, 8, 32, 128] {
+        let items: Vec<(String, Option<Vec<u8>>)> = (0..n)
+            .map(|i| (format!("commitments/{}", i), Some(i.to_be_bytes().to_vec())))
+            .collect();
+        let paths: Vec<&str> = items.iter().map(|(path, _)| path.as_str()).collect();
+        let proof = state.ibc_commitment_multiproof(&IBC_STORE_ADDRESS, &paths);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &items, |b, items| {
+            b.iter(|| {
+                client_state
+                    .verify_memberships(proof.clone(), root, items)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_header_parsing,
+    bench_verify_signature,
+    bench_update_client,
+    bench_account_proof,
+    bench_verify_memberships
+);
+criterion_main!(benches);
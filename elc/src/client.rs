@@ -1,23 +1,48 @@
 use crate::client_state::ClientState;
-use crate::commitment::{keccak256, verify_signature};
-use crate::consensus_state::ConsensusState;
+use crate::commitment::{calculate_memberships_commitment, keccak256};
+use crate::consensus_state::{ConsensusState, ValidatorSet};
 use crate::errors::Error;
 use crate::header::EthHeader;
 use crate::internal_prelude::*;
-use crate::message::Header;
-use crate::types::{gen_state_id, Address, H256};
+use crate::message::{ClientMessage, Header};
+use crate::path::MEMBERSHIPS_PATH;
+use crate::seal::{verify_commit_seals, CommitSealsReport, SealRecoveryMode};
+use crate::types::gen_state_id;
+use core::time::Duration;
 use light_client::commitments::{
-    EmittedState, TrustingPeriodContext, UpdateStateProxyMessage, ValidationContext,
+    CommitmentPrefix, EmittedState, MisbehaviourProxyMessage, PrevState, StateID,
+    TrustingPeriodContext, UpdateStateProxyMessage, ValidationContext,
     VerifyMembershipProxyMessage,
 };
 use light_client::{
-    types::{ClientId, Height, Time},
-    CreateClientResult, HostClientReader, LightClient, UpdateStateData, VerifyMembershipResult,
-    VerifyNonMembershipResult,
+    types::{Any, ClientId, Height, Time},
+    CreateClientResult, ErrorDetail, HostClientReader, LightClient, MisbehaviourData,
+    UpdateClientResult, UpdateStateData, VerifyMembershipResult, VerifyNonMembershipResult,
 };
 
 pub struct BesuQBFTLightClient;
 
+/// result of `BesuQBFTLightClient::dry_run_update_client`, which is the would-be result of `update_client`
+#[derive(Clone, Debug)]
+pub struct DryRunUpdateClientResult {
+    pub height: Height,
+    pub new_client_state: ClientState,
+    pub new_consensus_state: ConsensusState,
+    pub prev_state_id: StateID,
+    pub post_state_id: StateID,
+    /// true if the header is a backfill, which does not advance the latest height of the client
+    pub is_backfill: bool,
+    pub report: CommitSealsReport,
+}
+
+/// header verified against the trusted consensus state
+struct VerifiedHeader {
+    height: Height,
+    consensus_state: ConsensusState,
+    validation_context: ValidationContext,
+    report: CommitSealsReport,
+}
+
 impl LightClient for BesuQBFTLightClient {
     fn client_type(&self) -> String {
         "hb-qbft".to_string()
@@ -34,15 +59,17 @@ impl LightClient for BesuQBFTLightClient {
 
     fn create_client(
         &self,
-        _: &dyn light_client::HostClientReader,
+        ctx: &dyn light_client::HostClientReader,
         any_client_state: light_client::types::Any,
         any_consensus_state: light_client::types::Any,
     ) -> Result<light_client::CreateClientResult, light_client::Error> {
         let client_state = ClientState::try_from(any_client_state.clone())?;
-        let consensus_state = ConsensusState::try_from(any_consensus_state)?;
-
         client_state.validate()?;
+        // the initial consensus state is derived in the enclave if a checkpoint is given
+        let consensus_state = client_state
+            .consensus_state_from_any(any_consensus_state, client_state.latest_height)?;
         consensus_state.validate()?;
+        client_state.validate_initial_consensus_state(&consensus_state, ctx.host_timestamp())?;
 
         let height = client_state.latest_height;
 
@@ -70,86 +97,22 @@ impl LightClient for BesuQBFTLightClient {
         client_id: light_client::types::ClientId,
         client_message: light_client::types::Any,
     ) -> Result<light_client::UpdateClientResult, light_client::Error> {
-        let header: Header = client_message.try_into()?;
-        let client_state: ClientState = ctx.client_state(&client_id)?.try_into()?;
-        let trusted_consensus_state: ConsensusState = ctx
-            .consensus_state(&client_id, &header.trusted_height)?
-            .try_into()?;
-
-        let eth_header = EthHeader::parse(header.besu_header_rlp.as_slice())?;
-        let commit_hash = eth_header.commit_hash()?;
-
-        Self::verify_commit_seals_trusting(
-            &trusted_consensus_state.validators,
-            &header.seals,
-            commit_hash,
-        )?;
-        Self::verify_commit_seals_untrusting(
-            &eth_header.extra.validators,
-            &header.seals,
-            commit_hash,
-        )?;
-
-        let storage_root = client_state.verify_account_storage(
-            header.account_state_proof,
-            eth_header.state_root,
-            &client_state.ibc_store_address,
-        )?;
-
-        let mut new_client_state = client_state.clone();
-        let height = Height::new(
-            client_state.latest_height.revision_number(),
-            eth_header
-                .number
-                .try_into()
-                .map_err(Error::FromUint64Error)?,
-        );
-        if client_state.latest_height < height {
-            new_client_state.latest_height = height;
-        }
-        let timestamp: u128 = eth_header
-            .timestamp
-            .try_into()
-            .map_err(Error::FromUint128Error)?;
-        let new_consensus_state = ConsensusState {
-            timestamp: Time::from_unix_timestamp_nanos(timestamp * 1_000_000_000u128)
-                .map_err(Error::Time)?,
-            root: storage_root,
-            validators: eth_header.extra.validators,
-        };
-
-        let validation_context = if client_state.trusting_period.is_zero() {
-            ValidationContext::Empty
-        } else {
-            ValidationContext::TrustingPeriod(TrustingPeriodContext::new(
-                client_state.trusting_period,
-                client_state.max_clock_drift,
-                new_consensus_state.timestamp,
-                trusted_consensus_state.timestamp,
-            ))
-        };
-        validation_context
-            .validate(ctx.host_timestamp())
-            .map_err(Error::Commitments)?;
-
-        Ok(UpdateStateData {
-            new_any_client_state: new_client_state.clone().into(),
-            new_any_consensus_state: new_consensus_state.clone().into(),
-            height,
-            message: UpdateStateProxyMessage {
-                prev_height: Some(header.trusted_height),
-                prev_state_id: Some(gen_state_id(client_state, trusted_consensus_state)?),
-                post_height: height,
-                post_state_id: gen_state_id(new_client_state, new_consensus_state.clone())?,
-                emitted_states: Default::default(),
-                timestamp: new_consensus_state.timestamp,
-                context: validation_context,
-            },
-            prove: true,
+        match ClientMessage::try_from(client_message.clone())? {
+            ClientMessage::Header(header) => {
+                self.update_state(ctx, client_id, client_message, header, false)
+            }
+            ClientMessage::RevisionUpgrade(revision_upgrade) => self.update_state(
+                ctx,
+                client_id,
+                client_message,
+                revision_upgrade.header,
+                true,
+            ),
         }
-        .into())
     }
 
+    /// `LightClient` gives no delay period of the verification, so only `delay_time_period` of the client state is enforced,
+    /// which must be set to the delay period of the connection to enforce it through the LCP host
     fn verify_membership(
         &self,
         ctx: &dyn light_client::HostClientReader,
@@ -160,7 +123,61 @@ impl LightClient for BesuQBFTLightClient {
         proof_height: light_client::types::Height,
         proof: Vec<u8>,
     ) -> Result<light_client::VerifyMembershipResult, light_client::Error> {
-        let (client_state, consensus_state) = Self::validate_args(ctx, client_id, proof_height)?;
+        self.verify_membership_with_delay(
+            ctx,
+            client_id,
+            prefix,
+            path,
+            value,
+            proof_height,
+            proof,
+            Duration::ZERO,
+        )
+    }
+
+    /// see `verify_membership` for the delay period
+    fn verify_non_membership(
+        &self,
+        ctx: &dyn light_client::HostClientReader,
+        client_id: light_client::types::ClientId,
+        prefix: light_client::commitments::CommitmentPrefix,
+        path: String,
+        proof_height: light_client::types::Height,
+        proof: Vec<u8>,
+    ) -> Result<light_client::VerifyNonMembershipResult, light_client::Error> {
+        self.verify_non_membership_with_delay(
+            ctx,
+            client_id,
+            prefix,
+            path,
+            proof_height,
+            proof,
+            Duration::ZERO,
+        )
+    }
+}
+
+impl BesuQBFTLightClient {
+    /// verifies the membership as `verify_membership` after the delay period of the verification has passed
+    ///
+    /// `delay_time_period` is the delay period of the connection in ICS-07,
+    /// and the longer of it and `delay_time_period` of the client state is applied.
+    /// this is only reachable by the callers that link the light client directly, e.g. the tests and the relayers outside the enclave
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_membership_with_delay(
+        &self,
+        ctx: &dyn HostClientReader,
+        client_id: ClientId,
+        prefix: CommitmentPrefix,
+        path: String,
+        value: Vec<u8>,
+        proof_height: Height,
+        proof: Vec<u8>,
+        delay_time_period: Duration,
+    ) -> Result<VerifyMembershipResult, light_client::Error> {
+        Self::validate_path(&path)?;
+        let (client_state, consensus_state) =
+            Self::validate_args(ctx, client_id, proof_height, delay_time_period)?;
 
         client_state.verify_membership(proof, consensus_state.root, path.clone(), value.clone())?;
 
@@ -175,16 +192,22 @@ impl LightClient for BesuQBFTLightClient {
         })
     }
 
-    fn verify_non_membership(
+    /// verifies the non-membership as `verify_non_membership` after the delay period of the verification has passed
+    ///
+    /// see `verify_membership_with_delay` for `delay_time_period`
+    pub fn verify_non_membership_with_delay(
         &self,
-        ctx: &dyn light_client::HostClientReader,
-        client_id: light_client::types::ClientId,
-        prefix: light_client::commitments::CommitmentPrefix,
+        ctx: &dyn HostClientReader,
+        client_id: ClientId,
+        prefix: CommitmentPrefix,
         path: String,
-        proof_height: light_client::types::Height,
+        proof_height: Height,
         proof: Vec<u8>,
-    ) -> Result<light_client::VerifyNonMembershipResult, light_client::Error> {
-        let (client_state, consensus_state) = Self::validate_args(ctx, client_id, proof_height)?;
+        delay_time_period: Duration,
+    ) -> Result<VerifyNonMembershipResult, light_client::Error> {
+        Self::validate_path(&path)?;
+        let (client_state, consensus_state) =
+            Self::validate_args(ctx, client_id, proof_height, delay_time_period)?;
 
         client_state.verify_non_membership(proof, consensus_state.root, path.clone())?;
 
@@ -198,78 +221,1198 @@ impl LightClient for BesuQBFTLightClient {
             ),
         })
     }
-}
 
-impl BesuQBFTLightClient {
+    /// verifies the memberships of `items` against the consensus state at `proof_height` with a single proof
+    ///
+    /// an item whose value is `None` is verified as a non-membership.
+    /// the proxy message has `MEMBERSHIPS_PATH` as the path and `calculate_memberships_commitment(prefix, items)` as the value,
+    /// so the verifier of the message can check all the items against the commitment at once.
+    /// see `verify_membership_with_delay` for `delay_time_period`.
+    pub fn verify_memberships(
+        &self,
+        ctx: &dyn HostClientReader,
+        client_id: ClientId,
+        prefix: CommitmentPrefix,
+        items: Vec<(String, Option<Vec<u8>>)>,
+        proof_height: Height,
+        proof: Vec<u8>,
+        delay_time_period: Duration,
+    ) -> Result<VerifyMembershipResult, light_client::Error> {
+        for (path, _) in items.iter() {
+            Self::validate_path(path)?;
+        }
+        let (client_state, consensus_state) =
+            Self::validate_args(ctx, client_id, proof_height, delay_time_period)?;
+
+        client_state.verify_memberships(proof, consensus_state.root, &items)?;
+
+        let commitment = calculate_memberships_commitment(&prefix, &items);
+        Ok(VerifyMembershipResult {
+            message: VerifyMembershipProxyMessage::new(
+                prefix,
+                MEMBERSHIPS_PATH.to_string(),
+                Some(commitment.to_be_bytes()),
+                proof_height,
+                gen_state_id(client_state, consensus_state)?,
+            ),
+        })
+    }
+
+    /// runs the same verification as `update_client` against the given states, which is intended for the relayers and tests outside the enclave
+    ///
+    /// the header is verified with `SealRecoveryMode::Diagnostic`, so the report tells all the signers of the header.
+    /// if `upgrade_revision` is true, the header is verified as the one of `RevisionUpgrade`.
+    /// NOTE: the host may already have a consensus state at the height of the header, which cannot be known without `HostClientReader`,
+    /// so the header is always verified as a new one
+    pub fn dry_run_update_client(
+        &self,
+        client_state: &ClientState,
+        trusted_consensus_state: &ConsensusState,
+        header: Header,
+        upgrade_revision: bool,
+        host_timestamp: Time,
+    ) -> Result<DryRunUpdateClientResult, Error> {
+        let trusted_height = header.trusted_height;
+        let verified = Self::verify_header(
+            client_state,
+            trusted_consensus_state,
+            header,
+            upgrade_revision,
+            host_timestamp,
+            SealRecoveryMode::Diagnostic,
+        )?;
+        let is_backfill = Self::validate_update_order(
+            client_state,
+            trusted_height,
+            trusted_consensus_state.timestamp,
+            verified.height,
+            verified.consensus_state.timestamp,
+        )?;
+        let mut new_client_state = client_state.clone();
+        if !is_backfill {
+            new_client_state.latest_height = verified.height;
+        }
+        Ok(DryRunUpdateClientResult {
+            height: verified.height,
+            prev_state_id: gen_state_id(client_state.clone(), trusted_consensus_state.clone())?,
+            post_state_id: gen_state_id(
+                new_client_state.clone(),
+                verified.consensus_state.clone(),
+            )?,
+            new_client_state,
+            new_consensus_state: verified.consensus_state,
+            is_backfill,
+            report: verified.report,
+        })
+    }
+
+    fn update_state(
+        &self,
+        ctx: &dyn HostClientReader,
+        client_id: ClientId,
+        client_message: Any,
+        header: Header,
+        upgrade_revision: bool,
+    ) -> Result<UpdateClientResult, light_client::Error> {
+        let client_state: ClientState = ctx.client_state(&client_id)?.try_into()?;
+        let trusted_height = header.trusted_height;
+        let trusted_consensus_state = client_state.consensus_state_from_any(
+            ctx.consensus_state(&client_id, &trusted_height)?,
+            trusted_height,
+        )?;
+        let VerifiedHeader {
+            height,
+            consensus_state: new_consensus_state,
+            validation_context,
+            ..
+        } = Self::verify_header(
+            &client_state,
+            &trusted_consensus_state,
+            header,
+            upgrade_revision,
+            ctx.host_timestamp(),
+            SealRecoveryMode::EarlyExit,
+        )?;
+
+        // if a consensus state already exists at the height, the same update is a no-op
+        // and a different one is a misbehaviour that freezes the client
+        let mut new_client_state = client_state.clone();
+        let new_consensus_state = match ctx.consensus_state(&client_id, &height) {
+            Ok(existing_consensus_state) => {
+                let existing_consensus_state =
+                    client_state.consensus_state_from_any(existing_consensus_state, height)?;
+                if !existing_consensus_state.is_equivalent(&new_consensus_state) {
+                    let mut prev_states = vec![PrevState {
+                        height: trusted_height,
+                        state_id: gen_state_id(client_state.clone(), trusted_consensus_state)?,
+                    }];
+                    if height != trusted_height {
+                        prev_states.push(PrevState {
+                            height,
+                            state_id: gen_state_id(client_state, existing_consensus_state)?,
+                        });
+                    }
+                    new_client_state.frozen_height = Height::new(0, 1);
+                    return Ok(UpdateClientResult::Misbehaviour(MisbehaviourData {
+                        new_any_client_state: new_client_state.into(),
+                        message: MisbehaviourProxyMessage {
+                            prev_states,
+                            context: validation_context,
+                            client_message,
+                        },
+                    }));
+                }
+                existing_consensus_state
+            }
+            Err(e) if matches!(e.detail(), ErrorDetail::ConsensusStateNotFound(_)) => {
+                let is_backfill = Self::validate_update_order(
+                    &client_state,
+                    trusted_height,
+                    trusted_consensus_state.timestamp,
+                    height,
+                    new_consensus_state.timestamp,
+                )?;
+                if !is_backfill {
+                    new_client_state.latest_height = height;
+                }
+                new_consensus_state
+            }
+            Err(e) => return Err(e),
+        };
+
+        // the client state is only emitted if the update changes it
+        let emitted_states = if client_state.emit_client_state && new_client_state != client_state {
+            vec![EmittedState(height, new_client_state.clone().into())]
+        } else {
+            Default::default()
+        };
+
+        Ok(UpdateStateData {
+            new_any_client_state: new_client_state.clone().into(),
+            new_any_consensus_state: new_consensus_state.clone().try_into()?,
+            height,
+            message: UpdateStateProxyMessage {
+                prev_height: Some(trusted_height),
+                prev_state_id: Some(gen_state_id(client_state, trusted_consensus_state)?),
+                post_height: height,
+                post_state_id: gen_state_id(new_client_state, new_consensus_state.clone())?,
+                emitted_states,
+                timestamp: new_consensus_state.timestamp,
+                context: validation_context,
+            },
+            prove: true,
+        }
+        .into())
+    }
+
+    /// verifies `header` against the trusted consensus state, and returns the new consensus state
+    ///
+    /// this does not depend on the consensus states stored in the host, so it is shared by `update_client` and `dry_run_update_client`,
+    /// and it is the only place that rejects the update of a frozen client
+    fn verify_header(
+        client_state: &ClientState,
+        trusted_consensus_state: &ConsensusState,
+        header: Header,
+        upgrade_revision: bool,
+        host_timestamp: Time,
+        mode: SealRecoveryMode,
+    ) -> Result<VerifiedHeader, Error> {
+        if client_state.is_frozen() {
+            return Err(Error::ClientFrozen(client_state.frozen_height));
+        }
+        let next_revision_height = if upgrade_revision {
+            if header.trusted_height.revision_number()
+                != client_state.latest_height.revision_number()
+            {
+                return Err(Error::RevisionUpgradeTrustedHeightNotInLatestRevision {
+                    latest_height: client_state.latest_height,
+                    trusted_height: header.trusted_height,
+                });
+            }
+            Some(client_state.next_revision_height()?)
+        } else {
+            None
+        };
+
+        let eth_header = EthHeader::parse(header.besu_header_rlp.as_slice())?;
+        let commit_hash = eth_header.commit_hash()?;
+        let trusted_validators = trusted_consensus_state
+            .validators
+            .resolve(&header.trusted_validators)?;
+
+        let report = verify_commit_seals(
+            &trusted_validators,
+            &eth_header.extra.validators,
+            &header.seals,
+            commit_hash,
+            mode,
+        )?;
+
+        let storage_root = client_state.verify_account_storage(
+            header.account_state_proof,
+            eth_header.state_root,
+            &client_state.ibc_store_address,
+        )?;
+
+        let height = Height::new(
+            next_revision_height.map_or(client_state.latest_height.revision_number(), |height| {
+                height.revision_number()
+            }),
+            eth_header
+                .number
+                .try_into()
+                .map_err(Error::FromUint64Error)?,
+        );
+        if let Some(next_revision_height) = next_revision_height {
+            if height != next_revision_height {
+                return Err(Error::RevisionUpgradeHeightMismatch {
+                    expected: next_revision_height,
+                    actual: height,
+                });
+            }
+        }
+        let consensus_state = ConsensusState {
+            timestamp: client_state.header_timestamp(eth_header.timestamp)?,
+            root: storage_root,
+            validators: ValidatorSet::new(
+                eth_header.extra.validators,
+                client_state.use_validators_hash,
+            ),
+            processed_time: Some(host_timestamp),
+        };
+
+        let validation_context = if client_state.trusting_period.is_zero() {
+            ValidationContext::Empty
+        } else {
+            ValidationContext::TrustingPeriod(TrustingPeriodContext::new(
+                client_state.trusting_period,
+                client_state.max_clock_drift,
+                consensus_state.timestamp,
+                trusted_consensus_state.timestamp,
+            ))
+        };
+        validation_context
+            .validate(host_timestamp)
+            .map_err(Error::Commitments)?;
+
+        Ok(VerifiedHeader {
+            height,
+            consensus_state,
+            validation_context,
+            report,
+        })
+    }
+
     fn validate_args(
         ctx: &dyn HostClientReader,
         client_id: ClientId,
         proof_height: Height,
+        delay_time_period: Duration,
     ) -> Result<(ClientState, ConsensusState), light_client::Error> {
         let client_state: ClientState = ctx.client_state(&client_id)?.try_into()?;
+        if client_state.is_frozen() {
+            return Err(Error::ClientFrozen(client_state.frozen_height).into());
+        }
 
-        let consensus_state: ConsensusState =
-            ctx.consensus_state(&client_id, &proof_height)?.try_into()?;
+        let consensus_state = client_state.consensus_state_from_any(
+            ctx.consensus_state(&client_id, &proof_height)?,
+            proof_height,
+        )?;
+        let host_timestamp = ctx.host_timestamp();
+        client_state.verify_within_trusting_period(&consensus_state, host_timestamp)?;
+        client_state.verify_delay_passed(&consensus_state, host_timestamp, delay_time_period)?;
 
         Ok((client_state, consensus_state))
     }
 
-    fn verify_commit_seals_trusting(
-        trusted_validators: &[Address],
-        committed_seals: &[Vec<u8>],
-        commit_hash: H256,
-    ) -> Result<(), Error> {
-        let mut marked = vec![false; trusted_validators.len()];
-        let mut success = 0;
-        for seal in committed_seals.iter().filter(|seal| !seal.is_empty()) {
-            let addr = verify_signature(commit_hash, seal)?;
-            if let Some(pos) = trusted_validators.iter().position(|v| v == &addr) {
-                if !marked[pos] {
-                    marked[pos] = true;
-                    success += 1;
-                }
-            }
+    /// rejects `MEMBERSHIPS_PATH`, so the proxy message of a single verification cannot be confused with the one of a batch
+    fn validate_path(path: &str) -> Result<(), light_client::Error> {
+        if path == MEMBERSHIPS_PATH {
+            return Err(Error::ReservedMembershipPath(path.to_string()).into());
         }
-        if success * 3 <= trusted_validators.len() {
-            Err(Error::InsufficientTrustedValidatorsSeals {
-                actual: success * 3,
-                threshold: trusted_validators.len(),
-            })
+        Ok(())
+    }
+
+    /// validates the height and timestamp of the new consensus state against the trusted one
+    ///
+    /// a forward update must have a greater height and a timestamp not less than the trusted consensus state.
+    /// an update whose height is not greater than the latest height is a backfill, which is only accepted if `allow_backfill` is enabled.
+    /// a backfill below the trusted height must have a timestamp not greater than the trusted consensus state.
+    /// the timestamps are compared inclusively because QBFT allows consecutive blocks to share a timestamp,
+    /// which happens if the block period is shorter than the precision of the timestamp
+    ///
+    /// returns true if the update is a backfill, which does not advance the latest height of the client
+    fn validate_update_order(
+        client_state: &ClientState,
+        trusted_height: Height,
+        trusted_timestamp: Time,
+        height: Height,
+        timestamp: Time,
+    ) -> Result<bool, Error> {
+        if height > trusted_height {
+            if timestamp < trusted_timestamp {
+                return Err(Error::HeaderTimestampLessThanTrustedTimestamp {
+                    trusted_timestamp,
+                    header_timestamp: timestamp,
+                });
+            }
+        } else if height < trusted_height && client_state.allow_backfill {
+            if timestamp > trusted_timestamp {
+                return Err(Error::BackfillHeaderTimestampGreaterThanTrustedTimestamp {
+                    trusted_timestamp,
+                    header_timestamp: timestamp,
+                });
+            }
         } else {
-            Ok(())
+            return Err(Error::HeaderHeightNotGreaterThanTrustedHeight {
+                trusted_height,
+                header_height: height,
+            });
+        }
+        let is_backfill = height <= client_state.latest_height;
+        if is_backfill && !client_state.allow_backfill {
+            return Err(Error::BackfillNotAllowed {
+                latest_height: client_state.latest_height,
+                header_height: height,
+            });
         }
+        Ok(is_backfill)
     }
+}
 
-    /// CONTRACT: the order of `committed_seals` must be corresponding to the order of `validators`
-    fn verify_commit_seals_untrusting(
-        untrusted_validators: &[Address],
-        committed_seals: &[Vec<u8>],
-        commit_hash: H256,
-    ) -> Result<(), Error> {
-        if untrusted_validators.len() != committed_seals.len() {
-            return Err(Error::UntrustedValidatorsAndCommittedSealsLengthMismatch {
-                untrusted_validators_len: untrusted_validators.len(),
-                committed_seals_len: committed_seals.len(),
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{Checkpoint, RevisionUpgrade};
+    use crate::test_utils::qbft::{generate_validators, QbftChainSimulator};
+    use crate::test_utils::trie::MockStateTrie;
+    use crate::test_utils::MockContext;
+    use crate::types::{H256, U256};
+    use core::time::Duration;
+    use hex_literal::hex;
+    use light_client::ClientReader;
+
+    fn time(secs: u64) -> Time {
+        Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap()
+    }
+
+    fn client_id() -> ClientId {
+        "hb-qbft-0".parse().unwrap()
+    }
+
+    fn client_state() -> ClientState {
+        ClientState {
+            chain_id: U256::from(2018u64),
+            ibc_store_address: hex!("702e40245797c5a2108a566b3ce2bf14bc6af841"),
+            latest_height: Height::new(0, 100),
+            trusting_period: Duration::from_secs(100),
+            max_clock_drift: Duration::from_secs(10),
+            ..Default::default()
+        }
+    }
+
+    fn consensus_state(secs: u64) -> ConsensusState {
+        ConsensusState {
+            timestamp: time(secs),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: ValidatorSet::Addresses(vec![
+                hex!("647bfdd19655e51e69d35454ff3a92f8828e6302"),
+                hex!("a5c8416b9d13417b45b45ada76408f39d1e504ef"),
+                hex!("b92e91f4dcc9d28503be521afa2a8fbf3c1acf60"),
+                hex!("ee3353e587cfa91625a1adaef308a726de3803d3"),
+            ]),
+            processed_time: None,
+        }
+    }
+
+    /// creates the client with the block at height 100 of a simulated chain, whose blocks have the state `state`
+    fn setup(client_state: ClientState) -> (MockContext, QbftChainSimulator, MockStateTrie) {
+        let ibc_store_address = client_state.ibc_store_address;
+        let mut state = MockStateTrie::new();
+        state.set_ibc_commitment(ibc_store_address, "commitments/0", b"value");
+        let mut chain = QbftChainSimulator::new(generate_validators(4), 99, 990);
+        let trusted = chain.next_block(state.state_root());
+        let consensus_state = ConsensusState {
+            timestamp: time(trusted.timestamp),
+            root: state.storage_root(&ibc_store_address),
+            validators: ValidatorSet::Addresses(trusted.validators.clone()),
+            processed_time: None,
+        };
+        let mut ctx = MockContext::new(time(1000));
+        ctx.apply_create_client(
+            &client_id(),
+            client_state.into(),
+            consensus_state.try_into().unwrap(),
+            Height::new(0, 100),
+        );
+        (ctx, chain, state)
+    }
+
+    #[test]
+    fn test_create_client() {
+        let lc = BesuQBFTLightClient;
+        let mut ctx = MockContext::new(time(1000));
+
+        let res = lc
+            .create_client(
+                &ctx,
+                client_state().into(),
+                consensus_state(995).try_into().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(res.height, Height::new(0, 100));
+        ctx.apply_create_client(
+            &client_id(),
+            client_state().into(),
+            consensus_state(995).try_into().unwrap(),
+            res.height,
+        );
+        assert_eq!(
+            lc.latest_height(&ctx, &client_id()).unwrap(),
+            Height::new(0, 100)
+        );
+
+        // the consensus state is coming from future
+        assert!(lc
+            .create_client(
+                &ctx,
+                client_state().into(),
+                consensus_state(1011).try_into().unwrap()
+            )
+            .is_err());
+        // the consensus state is already expired
+        assert!(lc
+            .create_client(
+                &ctx,
+                client_state().into(),
+                consensus_state(900).try_into().unwrap()
+            )
+            .is_err());
+        // the processed time must be within the clock drift from the host timestamp
+        let mut consensus_state = consensus_state(995);
+        consensus_state.processed_time = Some(time(1010));
+        assert!(lc
+            .create_client(
+                &ctx,
+                client_state().into(),
+                consensus_state.clone().try_into().unwrap()
+            )
+            .is_ok());
+        consensus_state.processed_time = Some(time(1011));
+        assert!(lc
+            .create_client(
+                &ctx,
+                client_state().into(),
+                consensus_state.try_into().unwrap()
+            )
+            .is_err());
+        // the client state is invalid
+        let mut invalid_client_state = client_state();
+        invalid_client_state.chain_id = U256::ZERO;
+        assert!(lc
+            .create_client(
+                &ctx,
+                invalid_client_state.into(),
+                consensus_state(995).try_into().unwrap()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_create_client_from_checkpoint() {
+        let lc = BesuQBFTLightClient;
+        let client_state = client_state();
+        let ibc_store_address = client_state.ibc_store_address;
+        let mut state = MockStateTrie::new();
+        state.set_ibc_commitment(ibc_store_address, "commitments/0", b"value");
+        let mut chain = QbftChainSimulator::new(generate_validators(4), 99, 990);
+        let block = chain.next_block(state.state_root());
+        let besu_header_rlp = block.sealed_header_rlp();
+        let checkpoint = Checkpoint {
+            block_hash: H256::from_be_bytes(keccak256(&besu_header_rlp)),
+            besu_header_rlp,
+            account_state_proof: state.account_proof(&ibc_store_address),
+        };
+        let ctx = MockContext::new(time(1000));
+        let create_client = |checkpoint: Checkpoint| {
+            lc.create_client(&ctx, client_state.clone().into(), checkpoint.into())
+        };
+
+        // the header does not match the block hash
+        let mut invalid = checkpoint.clone();
+        invalid.besu_header_rlp = block.header_rlp.clone();
+        assert!(create_client(invalid).is_err());
+        // the header is not at the latest height of the client state
+        let other_block = chain.build_block(101, 992, state.state_root());
+        let mut invalid = checkpoint.clone();
+        invalid.besu_header_rlp = other_block.sealed_header_rlp();
+        invalid.block_hash = H256::from_be_bytes(keccak256(&invalid.besu_header_rlp));
+        assert!(create_client(invalid).is_err());
+        // the account proof is not for the state of the header
+        let mut other_state = MockStateTrie::new();
+        other_state.set_ibc_commitment(ibc_store_address, "commitments/0", b"other");
+        let mut invalid = checkpoint.clone();
+        invalid.account_state_proof = other_state.account_proof(&ibc_store_address);
+        assert!(create_client(invalid).is_err());
+
+        // the initial consensus state is derived from the checkpoint in the enclave
+        let consensus_state = client_state
+            .consensus_state_from_checkpoint(checkpoint.clone(), client_state.latest_height)
+            .unwrap();
+        assert_eq!(consensus_state.timestamp, time(block.timestamp));
+        assert_eq!(consensus_state.root, state.storage_root(&ibc_store_address));
+        let res = create_client(checkpoint.clone()).unwrap();
+        assert_eq!(res.height, Height::new(0, 100));
+
+        // the host stores the checkpoint as-is, and it is read as the derived consensus state
+        let mut ctx = MockContext::new(time(1000));
+        ctx.apply_create_client(
+            &client_id(),
+            client_state.clone().into(),
+            checkpoint.into(),
+            res.height,
+        );
+        assert_eq!(
+            client_state
+                .consensus_state_from_any(
+                    ctx.consensus_state(&client_id(), &res.height).unwrap(),
+                    res.height
+                )
+                .unwrap(),
+            consensus_state
+        );
+        assert!(lc
+            .verify_membership(
+                &ctx,
+                client_id(),
+                b"ibc".to_vec(),
+                "commitments/0".to_string(),
+                b"value".to_vec(),
+                Height::new(0, 100),
+                state.ibc_commitment_proof(&ibc_store_address, "commitments/0"),
+            )
+            .is_ok());
+
+        let block = chain.next_block(state.state_root());
+        let res = lc
+            .update_client(
+                &ctx,
+                client_id(),
+                block
+                    .to_header(Height::new(0, 100), state.account_proof(&ibc_store_address))
+                    .into(),
+            )
+            .unwrap();
+        ctx.apply_update_client(&client_id(), res);
+        assert_eq!(
+            lc.latest_height(&ctx, &client_id()).unwrap(),
+            Height::new(0, 101)
+        );
+    }
+
+    #[test]
+    fn test_validate_args() {
+        let mut ctx = MockContext::new(time(1000));
+        let height = Height::new(0, 100);
+        ctx.apply_create_client(
+            &client_id(),
+            client_state().into(),
+            consensus_state(995).try_into().unwrap(),
+            height,
+        );
+        let validate_args = |ctx: &MockContext, height: Height, delay_time_period: u64| {
+            BesuQBFTLightClient::validate_args(
+                ctx,
+                client_id(),
+                height,
+                Duration::from_secs(delay_time_period),
+            )
+        };
+
+        assert!(validate_args(&ctx, height, 0).is_ok());
+        assert!(validate_args(&ctx, Height::new(0, 99), 0).is_err());
+        // the processed time of the consensus state is required to verify the delay period
+        assert!(validate_args(&ctx, height, 1).is_err());
+
+        // the consensus state is expired
+        ctx.advance_host_timestamp(Duration::from_secs(95));
+        assert!(validate_args(&ctx, height, 0).is_err());
+
+        // the delay period has not passed
+        let mut consensus_state = consensus_state(995);
+        consensus_state.processed_time = Some(time(1000));
+        ctx.store_consensus_state(&client_id(), height, consensus_state.try_into().unwrap());
+        let mut client_state = client_state();
+        client_state.delay_time_period = Duration::from_secs(10);
+        ctx.store_client_state(&client_id(), client_state.clone().into());
+        ctx.set_host_timestamp(time(1009));
+        assert!(validate_args(&ctx, height, 0).is_err());
+        ctx.set_host_timestamp(time(1010));
+        assert!(validate_args(&ctx, height, 0).is_ok());
+        // the delay period of the verification is longer than the one of the client state
+        assert!(validate_args(&ctx, height, 20).is_err());
+        ctx.set_host_timestamp(time(1020));
+        assert!(validate_args(&ctx, height, 20).is_ok());
+
+        // the client is frozen
+        client_state.frozen_height = Height::new(0, 1);
+        ctx.store_client_state(&client_id(), client_state.into());
+        assert!(validate_args(&ctx, height, 0).is_err());
+    }
+
+    #[test]
+    fn test_verify_memberships() {
+        let lc = BesuQBFTLightClient;
+        let (ctx, _, state) = setup(client_state());
+        let address = client_state().ibc_store_address;
+        let prefix = b"ibc".to_vec();
+        let paths = ["commitments/0", "commitments/1"];
+        let proof = state.ibc_commitment_multiproof(&address, &paths);
+        let verify = |items: Vec<(String, Option<Vec<u8>>)>| {
+            lc.verify_memberships(
+                &ctx,
+                client_id(),
+                prefix.clone(),
+                items,
+                Height::new(0, 100),
+                proof.clone(),
+                Duration::ZERO,
+            )
+        };
+
+        let items = vec![
+            (paths[0].to_string(), Some(b"value".to_vec())),
+            (paths[1].to_string(), None),
+        ];
+        let message = verify(items.clone()).unwrap().message;
+        assert_eq!(message.prefix, prefix);
+        assert_eq!(message.path, MEMBERSHIPS_PATH);
+        assert_eq!(
+            message.value,
+            Some(calculate_memberships_commitment(&prefix, &items).to_be_bytes())
+        );
+        assert_eq!(message.height, Height::new(0, 100));
+        assert_eq!(
+            message.state_id,
+            gen_state_id(
+                client_state(),
+                ctx.consensus_state(&client_id(), &Height::new(0, 100))
+                    .unwrap()
+                    .try_into()
+                    .unwrap()
+            )
+            .unwrap()
+        );
+
+        // a single failing item fails the whole batch
+        assert!(verify(vec![
+            (paths[0].to_string(), Some(b"other".to_vec())),
+            (paths[1].to_string(), None),
+        ])
+        .is_err());
+        assert!(verify(vec![
+            (paths[0].to_string(), Some(b"value".to_vec())),
+            (paths[1].to_string(), Some(b"value".to_vec())),
+        ])
+        .is_err());
+
+        // the path of the batch cannot be verified as a single item
+        assert!(verify(vec![(MEMBERSHIPS_PATH.to_string(), None)]).is_err());
+        assert!(lc
+            .verify_non_membership(
+                &ctx,
+                client_id(),
+                prefix.clone(),
+                MEMBERSHIPS_PATH.to_string(),
+                Height::new(0, 100),
+                state.ibc_commitment_proof(&address, MEMBERSHIPS_PATH),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_update_client_frozen() {
+        let lc = BesuQBFTLightClient;
+        let mut ctx = MockContext::new(time(1000));
+        let mut client_state = client_state();
+        client_state.frozen_height = Height::new(0, 1);
+        ctx.apply_create_client(
+            &client_id(),
+            client_state.into(),
+            consensus_state(995).try_into().unwrap(),
+            Height::new(0, 100),
+        );
+        let header = Header {
+            besu_header_rlp: vec![],
+            seals: vec![],
+            trusted_height: Height::new(0, 100),
+            account_state_proof: vec![],
+            trusted_validators: vec![],
+        };
+        assert!(lc.update_client(&ctx, client_id(), header.into()).is_err());
+    }
+
+    #[test]
+    fn test_validate_update_order() {
+        let mut client_state = ClientState {
+            latest_height: Height::new(0, 100),
+            ..Default::default()
+        };
+        let trusted_height = Height::new(0, 50);
+        let validate = |client_state: &ClientState, height: u64, timestamp: u64| {
+            BesuQBFTLightClient::validate_update_order(
+                client_state,
+                trusted_height,
+                time(1000),
+                Height::new(0, height),
+                time(timestamp),
+            )
+        };
+
+        assert!(!validate(&client_state, 101, 1001).unwrap());
+        // consecutive blocks can share a timestamp
+        assert!(!validate(&client_state, 101, 1000).unwrap());
+        assert!(matches!(
+            validate(&client_state, 101, 999),
+            Err(Error::HeaderTimestampLessThanTrustedTimestamp { .. })
+        ));
+        assert!(matches!(
+            validate(&client_state, 50, 1001),
+            Err(Error::HeaderHeightNotGreaterThanTrustedHeight { .. })
+        ));
+        assert!(matches!(
+            validate(&client_state, 49, 999),
+            Err(Error::HeaderHeightNotGreaterThanTrustedHeight { .. })
+        ));
+        assert!(matches!(
+            validate(&client_state, 100, 1001),
+            Err(Error::BackfillNotAllowed { .. })
+        ));
+
+        client_state.allow_backfill = true;
+        assert!(!validate(&client_state, 101, 1001).unwrap());
+        assert!(validate(&client_state, 100, 1001).unwrap());
+        assert!(validate(&client_state, 49, 999).unwrap());
+        assert!(validate(&client_state, 49, 1000).unwrap());
+        assert!(matches!(
+            validate(&client_state, 49, 1001),
+            Err(Error::BackfillHeaderTimestampGreaterThanTrustedTimestamp { .. })
+        ));
+        assert!(matches!(
+            validate(&client_state, 50, 1000),
+            Err(Error::HeaderHeightNotGreaterThanTrustedHeight { .. })
+        ));
+    }
+
+    #[test]
+    fn test_update_client_same_timestamp() {
+        let lc = BesuQBFTLightClient;
+        let ibc_store_address = client_state().ibc_store_address;
+        let (mut ctx, chain, state) = setup(client_state());
+        let proof = state.account_proof(&ibc_store_address);
+        // the blocks are produced within the same second as the trusted block at height 100
+        let trusted_timestamp = 991;
+        for height in [101, 102] {
+            let block = chain.build_block(height, trusted_timestamp, state.state_root());
+            let res = lc
+                .update_client(
+                    &ctx,
+                    client_id(),
+                    block
+                        .to_header(Height::new(0, height - 1), proof.clone())
+                        .into(),
+                )
+                .unwrap();
+            ctx.apply_update_client(&client_id(), res);
+            assert_eq!(
+                lc.latest_height(&ctx, &client_id()).unwrap(),
+                Height::new(0, height)
+            );
+        }
+
+        // the block before the trusted one is still rejected
+        let block = chain.build_block(103, trusted_timestamp - 1, state.state_root());
+        assert!(lc
+            .update_client(
+                &ctx,
+                client_id(),
+                block.to_header(Height::new(0, 102), proof).into()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_update_client_backfill() {
+        let lc = BesuQBFTLightClient;
+        for allow_backfill in [false, true] {
+            let mut client_state = client_state();
+            client_state.allow_backfill = allow_backfill;
+            let ibc_store_address = client_state.ibc_store_address;
+            let (mut ctx, mut chain, state) = setup(client_state);
+            let backfill = chain.next_block(state.state_root());
+            let latest = chain.next_block(state.state_root());
+            let proof = state.account_proof(&ibc_store_address);
+
+            let res = lc
+                .update_client(
+                    &ctx,
+                    client_id(),
+                    latest.to_header(Height::new(0, 100), proof.clone()).into(),
+                )
+                .unwrap();
+            ctx.apply_update_client(&client_id(), res);
+            let client_state: ClientState =
+                ctx.client_state(&client_id()).unwrap().try_into().unwrap();
+            assert_eq!(client_state.latest_height, Height::new(0, 102));
+
+            // the header is newer than the trusted consensus state but not than the latest height
+            let header = backfill.to_header(Height::new(0, 100), proof);
+            let trusted_consensus_state: ConsensusState = ctx
+                .consensus_state(&client_id(), &Height::new(0, 100))
+                .unwrap()
+                .try_into()
+                .unwrap();
+            let res = lc.update_client(&ctx, client_id(), header.clone().into());
+            if allow_backfill {
+                ctx.apply_update_client(&client_id(), res.unwrap());
+                assert_eq!(
+                    lc.latest_height(&ctx, &client_id()).unwrap(),
+                    Height::new(0, 102)
+                );
+                assert_eq!(
+                    ctx.consensus_state_heights(&client_id()),
+                    vec![
+                        Height::new(0, 100),
+                        Height::new(0, 101),
+                        Height::new(0, 102)
+                    ]
+                );
+            } else {
+                // the clients created before `allow_backfill` was introduced accepted this header
+                assert!(res.is_err());
+                assert!(matches!(
+                    lc.dry_run_update_client(
+                        &client_state,
+                        &trusted_consensus_state,
+                        header,
+                        false,
+                        time(1000)
+                    ),
+                    Err(Error::BackfillNotAllowed { .. })
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_client_existing_height() {
+        let lc = BesuQBFTLightClient;
+        let ibc_store_address = client_state().ibc_store_address;
+        let (mut ctx, mut chain, state) = setup(client_state());
+        let block = chain.next_block(state.state_root());
+        let proof = state.account_proof(&ibc_store_address);
+        let header = block.to_header(Height::new(0, 100), proof.clone());
+
+        let res = lc
+            .update_client(&ctx, client_id(), header.clone().into())
+            .unwrap();
+        ctx.apply_update_client(&client_id(), res);
+        let client_state = ctx.client_state(&client_id()).unwrap();
+        let consensus_state = ctx
+            .consensus_state(&client_id(), &Height::new(0, 101))
+            .unwrap();
+
+        // the same header is a no-op
+        ctx.advance_host_timestamp(Duration::from_secs(1));
+        match lc
+            .update_client(&ctx, client_id(), header.clone().into())
+            .unwrap()
+        {
+            UpdateClientResult::UpdateState(data) => {
+                assert_eq!(data.height, Height::new(0, 101));
+                assert_eq!(data.new_any_client_state, client_state);
+                assert_eq!(data.new_any_consensus_state, consensus_state);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // a different header at the same height is a misbehaviour
+        let mut conflicting_state = state.clone();
+        conflicting_state.set_ibc_commitment(ibc_store_address, "commitments/1", b"value");
+        let conflicting_block = chain.build_block(
+            block.number,
+            block.timestamp,
+            conflicting_state.state_root(),
+        );
+        let conflicting_header = conflicting_block.to_header(
+            Height::new(0, 100),
+            conflicting_state.account_proof(&ibc_store_address),
+        );
+        let res = lc
+            .update_client(&ctx, client_id(), conflicting_header.clone().into())
+            .unwrap();
+        match &res {
+            UpdateClientResult::Misbehaviour(data) => {
+                let new_client_state =
+                    ClientState::try_from(data.new_any_client_state.clone()).unwrap();
+                assert!(new_client_state.is_frozen());
+                assert_eq!(
+                    data.message
+                        .prev_states
+                        .iter()
+                        .map(|prev_state| prev_state.height)
+                        .collect::<Vec<_>>(),
+                    vec![Height::new(0, 100), Height::new(0, 101)]
+                );
+                assert_eq!(data.message.client_message, conflicting_header.into());
+            }
+            res => panic!("unexpected result: {:?}", res),
         }
-        let mut success = 0;
-        for (validator, seal) in untrusted_validators
-            .iter()
-            .zip(committed_seals.iter())
-            .filter(|(_, seal)| !seal.is_empty())
+        ctx.apply_update_client(&client_id(), res);
+        assert!(lc.update_client(&ctx, client_id(), header.into()).is_err());
+    }
+
+    #[test]
+    fn test_update_client_revision_upgrade() {
+        let lc = BesuQBFTLightClient;
+        let mut client_state = client_state();
+        client_state.revision_schedule = vec![Height::new(1, 1)];
+        let ibc_store_address = client_state.ibc_store_address;
+        let (mut ctx, chain, state) = setup(client_state);
+        let proof = state.account_proof(&ibc_store_address);
+        // the network is restarted from a new genesis
+        let genesis = chain.build_block(1, 992, state.state_root());
+        let upgrade = |header: Header| -> Any {
+            ClientMessage::RevisionUpgrade(RevisionUpgrade { header }).into()
+        };
+
+        // a header is not accepted as a revision upgrade unless it is at the scheduled height
+        let block = chain.build_block(2, 992, state.state_root());
+        assert!(lc
+            .update_client(
+                &ctx,
+                client_id(),
+                upgrade(block.to_header(Height::new(0, 100), proof.clone()))
+            )
+            .is_err());
+        // the header of the new genesis is not accepted without a revision upgrade
+        assert!(lc
+            .update_client(
+                &ctx,
+                client_id(),
+                genesis.to_header(Height::new(0, 100), proof.clone()).into()
+            )
+            .is_err());
+
+        let res = lc
+            .update_client(
+                &ctx,
+                client_id(),
+                upgrade(genesis.to_header(Height::new(0, 100), proof.clone())),
+            )
+            .unwrap();
+        ctx.apply_update_client(&client_id(), res);
+        assert_eq!(
+            lc.latest_height(&ctx, &client_id()).unwrap(),
+            Height::new(1, 1)
+        );
+
+        // the next revision is not scheduled
+        let block = chain.build_block(2, 993, state.state_root());
+        assert!(lc
+            .update_client(
+                &ctx,
+                client_id(),
+                upgrade(block.to_header(Height::new(1, 1), proof.clone()))
+            )
+            .is_err());
+        let res = lc
+            .update_client(
+                &ctx,
+                client_id(),
+                block.to_header(Height::new(1, 1), proof).into(),
+            )
+            .unwrap();
+        ctx.apply_update_client(&client_id(), res);
+        assert_eq!(
+            lc.latest_height(&ctx, &client_id()).unwrap(),
+            Height::new(1, 2)
+        );
+    }
+
+    #[test]
+    fn test_update_client_emit_client_state() {
+        let lc = BesuQBFTLightClient;
+        let mut client_state = client_state();
+        client_state.emit_client_state = true;
+        client_state.allow_backfill = true;
+        let ibc_store_address = client_state.ibc_store_address;
+        let (mut ctx, mut chain, state) = setup(client_state);
+        let proof = state.account_proof(&ibc_store_address);
+        let backfill = chain.next_block(state.state_root());
+        let latest = chain.next_block(state.state_root());
+        let emitted_states =
+            |ctx: &MockContext, header: Header| -> (Vec<EmittedState>, UpdateClientResult) {
+                match lc.update_client(ctx, client_id(), header.into()).unwrap() {
+                    UpdateClientResult::UpdateState(data) => {
+                        (data.message.emitted_states.clone(), data.into())
+                    }
+                    res => panic!("unexpected result: {:?}", res),
+                }
+            };
+
+        // the latest height is advanced
+        let (emitted, res) =
+            emitted_states(&ctx, latest.to_header(Height::new(0, 100), proof.clone()));
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].0, Height::new(0, 102));
+        assert_eq!(
+            ClientState::try_from(emitted[0].1.clone())
+                .unwrap()
+                .latest_height,
+            Height::new(0, 102)
+        );
+        ctx.apply_update_client(&client_id(), res);
+
+        // the backfill does not change the client state
+        let (emitted, res) =
+            emitted_states(&ctx, backfill.to_header(Height::new(0, 100), proof.clone()));
+        assert!(emitted.is_empty());
+        ctx.apply_update_client(&client_id(), res);
+
+        // the same header is a no-op
+        let (emitted, _) = emitted_states(&ctx, latest.to_header(Height::new(0, 100), proof));
+        assert!(emitted.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_update_client() {
+        let lc = BesuQBFTLightClient;
+        let ibc_store_address = client_state().ibc_store_address;
+        let mut state = MockStateTrie::new();
+        state.set_ibc_commitment(ibc_store_address, "commitments/0", b"value");
+        let mut chain = QbftChainSimulator::new(generate_validators(4), 99, 990);
+        let trusted = chain.next_block(state.state_root());
+        let block = chain.next_block(state.state_root());
+
+        let client_state = client_state();
+        let trusted_consensus_state = ConsensusState {
+            timestamp: time(trusted.timestamp),
+            root: state.storage_root(&ibc_store_address),
+            validators: ValidatorSet::Addresses(trusted.validators.clone()),
+            processed_time: None,
+        };
+        let mut ctx = MockContext::new(time(1000));
+        ctx.apply_create_client(
+            &client_id(),
+            client_state.clone().into(),
+            trusted_consensus_state.clone().try_into().unwrap(),
+            Height::new(0, 100),
+        );
+        let header = block.to_header(Height::new(0, 100), state.account_proof(&ibc_store_address));
+
+        let res = lc
+            .dry_run_update_client(
+                &client_state,
+                &trusted_consensus_state,
+                header.clone(),
+                false,
+                time(1000),
+            )
+            .unwrap();
+        assert_eq!(res.height, Height::new(0, 101));
+        assert_eq!(res.new_client_state.latest_height, Height::new(0, 101));
+        assert!(!res.is_backfill);
+        assert_eq!(res.report.untrusted_signed, 4);
+        assert_eq!(res.report.trusted_signed, 4);
+
+        // the dry run returns the same states as update_client
+        match lc
+            .update_client(&ctx, client_id(), header.clone().into())
+            .unwrap()
         {
-            let addr = verify_signature(commit_hash, seal)?;
-            if addr == *validator {
-                success += 1;
+            UpdateClientResult::UpdateState(data) => {
+                assert_eq!(data.height, res.height);
+                assert_eq!(data.message.prev_state_id, Some(res.prev_state_id));
+                assert_eq!(data.message.post_state_id, res.post_state_id);
+                assert_eq!(
+                    ClientState::try_from(data.new_any_client_state).unwrap(),
+                    res.new_client_state
+                );
             }
+            res => panic!("unexpected result: {:?}", res),
         }
-        if success * 3 < untrusted_validators.len() * 2 {
-            Err(Error::InsuffientUntrustedValidatorsSeals {
-                actual: success * 3,
-                threshold: untrusted_validators.len() * 2,
-            })
-        } else {
-            Ok(())
+
+        // the report of the insufficient seals is returned in the error
+        let mut block = block.clone();
+        block.remove_seal(0);
+        block.remove_seal(1);
+        let header = block.to_header(Height::new(0, 100), state.account_proof(&ibc_store_address));
+        match lc.dry_run_update_client(
+            &client_state,
+            &trusted_consensus_state,
+            header,
+            false,
+            time(1000),
+        ) {
+            Err(Error::InsuffientUntrustedValidatorsSeals { report, .. }) => {
+                assert_eq!(
+                    report.missing_untrusted_validators(),
+                    block.validators[..2].to_vec()
+                );
+            }
+            res => panic!("unexpected result: {:?}", res),
         }
+
+        // the header of a new genesis is only accepted as a scheduled revision upgrade
+        let genesis = chain.build_block(1, 992, state.state_root());
+        let header =
+            genesis.to_header(Height::new(0, 100), state.account_proof(&ibc_store_address));
+        let mut upgradable_client_state = client_state.clone();
+        upgradable_client_state.revision_schedule = vec![Height::new(1, 1)];
+        let res = lc
+            .dry_run_update_client(
+                &upgradable_client_state,
+                &trusted_consensus_state,
+                header.clone(),
+                true,
+                time(1000),
+            )
+            .unwrap();
+        assert_eq!(res.height, Height::new(1, 1));
+        assert_eq!(res.new_client_state.latest_height, Height::new(1, 1));
+        assert!(lc
+            .dry_run_update_client(
+                &upgradable_client_state,
+                &trusted_consensus_state,
+                header.clone(),
+                false,
+                time(1000),
+            )
+            .is_err());
+        assert!(matches!(
+            lc.dry_run_update_client(
+                &client_state,
+                &trusted_consensus_state,
+                header.clone(),
+                true,
+                time(1000),
+            ),
+            Err(Error::RevisionUpgradeNotScheduled(_))
+        ));
+
+        // the frozen client is rejected as in update_client
+        let mut frozen_client_state = client_state.clone();
+        frozen_client_state.frozen_height = Height::new(0, 1);
+        assert!(matches!(
+            lc.dry_run_update_client(
+                &frozen_client_state,
+                &trusted_consensus_state,
+                header,
+                false,
+                time(1000),
+            ),
+            Err(Error::ClientFrozen(_))
+        ));
     }
 }
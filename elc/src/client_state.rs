@@ -1,16 +1,23 @@
 use crate::commitment::{
-    calculate_ibc_commitment_storage_key, decode_eip1184_rlp_proof, keccak256,
+    calculate_ibc_commitment_storage_key, calculate_ibc_commitment_storage_value,
+    decode_eip1184_rlp_proof, keccak256, ProofNodes,
 };
+use crate::consensus_state::{ConsensusState, ValidatorSet};
+use crate::header::EthHeader;
 use crate::internal_prelude::*;
+use crate::message::{Checkpoint, BESU_QBFT_CHECKPOINT_TYPE_URL};
 use crate::types::{Address, H256};
 use crate::{errors::Error, types::U256};
 use besu_qbft_proto::ibc::{
-    core::client::v1::Height as RawHeight, lightclients::qbft::v1::ClientState as RawClientState,
+    core::client::v1::Height as RawHeight,
+    lightclients::qbft::v1::{
+        ClientState as RawClientState, TimestampPrecision as RawTimestampPrecision,
+    },
 };
 use core::time::Duration;
 use ethereum_light_client_verifier::execution::ExecutionVerifier;
 use light_client::types::proto::google::protobuf::Any as ProtoAny;
-use light_client::types::{Any, Height};
+use light_client::types::{Any, Height, Time};
 use prost::Message;
 use serde::{Deserialize, Serialize};
 
@@ -23,10 +30,42 @@ pub struct ClientState {
     pub latest_height: Height,
     pub trusting_period: Duration,
     pub max_clock_drift: Duration,
+    pub use_validators_hash: bool,
+    pub allow_backfill: bool,
+    pub frozen_height: Height,
+    pub timestamp_precision: TimestampPrecision,
+    pub emit_client_state: bool,
+    pub delay_time_period: Duration,
+    pub revision_schedule: Vec<Height>,
     #[serde(skip)]
     pub execution_verifier: ExecutionVerifier,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampPrecision {
+    #[default]
+    Seconds,
+    Milliseconds,
+}
+
+impl From<TimestampPrecision> for RawTimestampPrecision {
+    fn from(value: TimestampPrecision) -> Self {
+        match value {
+            TimestampPrecision::Seconds => RawTimestampPrecision::Seconds,
+            TimestampPrecision::Milliseconds => RawTimestampPrecision::Milliseconds,
+        }
+    }
+}
+
+impl From<RawTimestampPrecision> for TimestampPrecision {
+    fn from(value: RawTimestampPrecision) -> Self {
+        match value {
+            RawTimestampPrecision::Seconds => TimestampPrecision::Seconds,
+            RawTimestampPrecision::Milliseconds => TimestampPrecision::Milliseconds,
+        }
+    }
+}
+
 impl From<ClientState> for RawClientState {
     fn from(value: ClientState) -> Self {
         RawClientState {
@@ -42,6 +81,30 @@ impl From<ClientState> for RawClientState {
             },
             trusting_period: value.trusting_period.as_secs(),
             max_clock_drift: value.max_clock_drift.as_secs(),
+            use_validators_hash: value.use_validators_hash,
+            allow_backfill: value.allow_backfill,
+            frozen_height: if value.frozen_height.is_zero() {
+                None
+            } else {
+                Some(RawHeight {
+                    revision_number: value.frozen_height.revision_number(),
+                    revision_height: value.frozen_height.revision_height(),
+                })
+            },
+            timestamp_precision: RawTimestampPrecision::from(value.timestamp_precision).into(),
+            trusting_period_nanos: value.trusting_period.subsec_nanos(),
+            max_clock_drift_nanos: value.max_clock_drift.subsec_nanos(),
+            emit_client_state: value.emit_client_state,
+            delay_time_period: value.delay_time_period.as_secs(),
+            delay_time_period_nanos: value.delay_time_period.subsec_nanos(),
+            revision_schedule: value
+                .revision_schedule
+                .into_iter()
+                .map(|height| RawHeight {
+                    revision_number: height.revision_number(),
+                    revision_height: height.revision_height(),
+                })
+                .collect(),
         }
     }
 }
@@ -51,7 +114,8 @@ impl TryFrom<RawClientState> for ClientState {
 
     fn try_from(value: RawClientState) -> Result<Self, Self::Error> {
         Ok(ClientState {
-            chain_id: U256::from_be_slice(&value.chain_id),
+            chain_id: U256::try_from_be_slice(&value.chain_id)
+                .ok_or_else(|| Error::InvalidClientStateChainIdSize(value.chain_id.len()))?,
             ibc_store_address: value
                 .ibc_store_address
                 .as_slice()
@@ -60,8 +124,34 @@ impl TryFrom<RawClientState> for ClientState {
             latest_height: value.latest_height.map_or(Height::zero(), |height| {
                 Height::new(height.revision_number, height.revision_height)
             }),
-            trusting_period: Duration::from_secs(value.trusting_period),
-            max_clock_drift: Duration::from_secs(value.max_clock_drift),
+            trusting_period: duration_from_parts(
+                value.trusting_period,
+                value.trusting_period_nanos,
+            )?,
+            max_clock_drift: duration_from_parts(
+                value.max_clock_drift,
+                value.max_clock_drift_nanos,
+            )?,
+            use_validators_hash: value.use_validators_hash,
+            allow_backfill: value.allow_backfill,
+            frozen_height: value.frozen_height.map_or(Height::zero(), |height| {
+                Height::new(height.revision_number, height.revision_height)
+            }),
+            timestamp_precision: RawTimestampPrecision::from_i32(value.timestamp_precision)
+                .ok_or(Error::InvalidClientStateTimestampPrecision(
+                    value.timestamp_precision,
+                ))?
+                .into(),
+            emit_client_state: value.emit_client_state,
+            delay_time_period: duration_from_parts(
+                value.delay_time_period,
+                value.delay_time_period_nanos,
+            )?,
+            revision_schedule: value
+                .revision_schedule
+                .into_iter()
+                .map(|height| Height::new(height.revision_number, height.revision_height))
+                .collect(),
             execution_verifier: ExecutionVerifier,
         })
     }
@@ -125,7 +215,7 @@ impl ClientState {
         self.execution_verifier.verify_membership(
             root.to_be_bytes().into(),
             key.to_be_bytes_vec().as_slice(),
-            rlp::encode(&trim_left_zero(keccak256(&value).as_slice())).as_ref(),
+            calculate_ibc_commitment_storage_value(&value).as_slice(),
             proof,
         )?;
 
@@ -150,30 +240,521 @@ impl ClientState {
         Ok(())
     }
 
+    /// verifies the memberships of `items` against `root` with a single proof
+    ///
+    /// `proof` is a list of trie nodes that covers all the paths of `items`, so the nodes shared by the paths only need to be included once.
+    /// the proof is decoded once and every item is looked up against the same nodes.
+    /// an item whose value is `None` is verified as a non-membership
+    pub fn verify_memberships(
+        &self,
+        proof: Vec<u8>,
+        root: H256,
+        items: &[(String, Option<Vec<u8>>)],
+    ) -> Result<(), Error> {
+        let nodes = ProofNodes::decode(&proof)?;
+        for (path, value) in items {
+            let key: [u8; 32] = calculate_ibc_commitment_storage_key(path.as_bytes()).to_be_bytes();
+            let expected = value.as_deref().map(calculate_ibc_commitment_storage_value);
+            let actual = nodes.get(root, &key)?;
+            if actual != expected.as_deref() {
+                return Err(Error::MembershipValueMismatch {
+                    path: path.clone(),
+                    expected,
+                    actual: actual.map(<[u8]>::to_vec),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// converts the timestamp in the Besu header into `Time` according to `timestamp_precision`
+    pub fn header_timestamp(&self, timestamp: U256) -> Result<Time, Error> {
+        let timestamp: u128 = timestamp.try_into().map_err(Error::FromUint128Error)?;
+        let timestamp_nanos = match self.timestamp_precision {
+            TimestampPrecision::Seconds => timestamp.checked_mul(1_000_000_000),
+            TimestampPrecision::Milliseconds => timestamp.checked_mul(1_000_000),
+        }
+        .ok_or(Error::HeaderTimestampOverflow(timestamp))?;
+        Ok(Time::from_unix_timestamp_nanos(timestamp_nanos)?)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        !self.frozen_height.is_zero()
+    }
+
     pub fn validate(&self) -> Result<(), Error> {
+        if self.chain_id.is_zero() {
+            return Err(Error::InvalidClientStateZeroChainId);
+        }
         if self.latest_height.is_zero() {
             return Err(Error::InvalidClientStateZeroHeight);
         }
         if self.ibc_store_address == Address::default() {
             return Err(Error::InvalidClientStateZeroIbcStoreAddress);
         }
+        if !self.trusting_period.is_zero() && self.max_clock_drift >= self.trusting_period {
+            return Err(
+                Error::InvalidClientStateMaxClockDriftNotLessThanTrustingPeriod {
+                    max_clock_drift: self.max_clock_drift,
+                    trusting_period: self.trusting_period,
+                },
+            );
+        }
+        if self.is_frozen() {
+            return Err(Error::InvalidClientStateFrozen(self.frozen_height));
+        }
+        let mut revision_number = self.latest_height.revision_number();
+        for height in self.revision_schedule.iter() {
+            if height.revision_number() <= revision_number || height.revision_height() == 0 {
+                return Err(Error::InvalidClientStateRevisionSchedule {
+                    latest_height: self.latest_height,
+                    revision_schedule: self.revision_schedule.clone(),
+                });
+            }
+            revision_number = height.revision_number();
+        }
+        Ok(())
+    }
+
+    /// returns the first height of the revision next to the latest height, which is scheduled in `revision_schedule`
+    pub fn next_revision_height(&self) -> Result<Height, Error> {
+        let revision_number = self.latest_height.revision_number() + 1;
+        self.revision_schedule
+            .iter()
+            .find(|height| height.revision_number() == revision_number)
+            .copied()
+            .ok_or(Error::RevisionUpgradeNotScheduled(self.latest_height))
+    }
+
+    /// derives the consensus state at `height` from the checkpoint
+    ///
+    /// the header must be the one of `block_hash`, the validators and the timestamp are taken from the header,
+    /// and the storage root is verified with the account proof.
+    /// the result only depends on the fields of the client state that are never changed by updates,
+    /// so the same consensus state is derived whenever the checkpoint is read from the host
+    pub fn consensus_state_from_checkpoint(
+        &self,
+        checkpoint: Checkpoint,
+        height: Height,
+    ) -> Result<ConsensusState, Error> {
+        let block_hash = H256::from_be_bytes(keccak256(&checkpoint.besu_header_rlp));
+        if block_hash != checkpoint.block_hash {
+            return Err(Error::CheckpointBlockHashMismatch {
+                expected: checkpoint.block_hash,
+                actual: block_hash,
+            });
+        }
+        let eth_header = EthHeader::parse(&checkpoint.besu_header_rlp)?;
+        let number: u64 = eth_header
+            .number
+            .try_into()
+            .map_err(Error::FromUint64Error)?;
+        if number != height.revision_height() {
+            return Err(Error::CheckpointHeightMismatch {
+                expected: height.revision_height(),
+                actual: number,
+            });
+        }
+        let root = self.verify_account_storage(
+            checkpoint.account_state_proof,
+            eth_header.state_root,
+            &self.ibc_store_address,
+        )?;
+        Ok(ConsensusState {
+            timestamp: self.header_timestamp(eth_header.timestamp)?,
+            root,
+            validators: ValidatorSet::new(eth_header.extra.validators, self.use_validators_hash),
+            processed_time: None,
+        })
+    }
+
+    /// converts the consensus state at `height` stored in the host
+    ///
+    /// the host stores the `Any` given to `create_client` as-is, so the initial consensus state may be a checkpoint
+    pub fn consensus_state_from_any(
+        &self,
+        any_consensus_state: Any,
+        height: Height,
+    ) -> Result<ConsensusState, Error> {
+        if any_consensus_state.type_url == BESU_QBFT_CHECKPOINT_TYPE_URL {
+            self.consensus_state_from_checkpoint(any_consensus_state.try_into()?, height)
+        } else {
+            any_consensus_state.try_into()
+        }
+    }
+
+    /// returns the time at which the trusting period of `consensus_state` ends
+    pub fn trusting_period_end(&self, consensus_state: &ConsensusState) -> Result<Time, Error> {
+        Ok(Time::from_unix_timestamp_nanos(
+            consensus_state.timestamp.as_unix_timestamp_nanos() + self.trusting_period.as_nanos(),
+        )?)
+    }
+
+    /// returns true if the trusting period of `consensus_state` has ended at `host_timestamp`
+    ///
+    /// a consensus state never expires if `trusting_period` is zero
+    pub fn is_expired(
+        &self,
+        consensus_state: &ConsensusState,
+        host_timestamp: Time,
+    ) -> Result<bool, Error> {
+        if self.trusting_period.is_zero() {
+            return Ok(false);
+        }
+        Ok(self.trusting_period_end(consensus_state)? <= host_timestamp)
+    }
+
+    /// verifies that the trusting period of `consensus_state` has not ended at `host_timestamp`
+    pub fn verify_within_trusting_period(
+        &self,
+        consensus_state: &ConsensusState,
+        host_timestamp: Time,
+    ) -> Result<(), Error> {
+        if self.is_expired(consensus_state, host_timestamp)? {
+            return Err(Error::OutOfTrustingPeriod {
+                current_timestamp: host_timestamp,
+                trusting_period_end: self.trusting_period_end(consensus_state)?,
+            });
+        }
         Ok(())
     }
+
+    /// verifies that the delay period has passed at `host_timestamp` since `consensus_state` was processed
+    ///
+    /// the delay period is the longer of `delay_time_period` of the client state and the one given by the verification,
+    /// which is the delay period of the connection in ICS-07
+    pub fn verify_delay_passed(
+        &self,
+        consensus_state: &ConsensusState,
+        host_timestamp: Time,
+        delay_time_period: Duration,
+    ) -> Result<(), Error> {
+        let delay_time_period = self.delay_time_period.max(delay_time_period);
+        if delay_time_period.is_zero() {
+            return Ok(());
+        }
+        let processed_time = consensus_state
+            .processed_time
+            .ok_or(Error::ProcessedTimeNotFound)?;
+        let valid_timestamp = Time::from_unix_timestamp_nanos(
+            processed_time.as_unix_timestamp_nanos() + delay_time_period.as_nanos(),
+        )?;
+        if host_timestamp < valid_timestamp {
+            return Err(Error::DelayPeriodNotPassed {
+                current_timestamp: host_timestamp,
+                valid_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// validates the timestamp and the processed time of the initial consensus state against the host timestamp
+    ///
+    /// NOTE: the LCP host stores the initial consensus state as-is, so the processed time cannot be set to the host timestamp by the client.
+    /// instead, the processed time given by the creator must be within `max_clock_drift` from the host timestamp
+    pub fn validate_initial_consensus_state(
+        &self,
+        consensus_state: &ConsensusState,
+        host_timestamp: Time,
+    ) -> Result<(), Error> {
+        let max_timestamp = Time::from_unix_timestamp_nanos(
+            host_timestamp.as_unix_timestamp_nanos() + self.max_clock_drift.as_nanos(),
+        )?;
+        if consensus_state.timestamp > max_timestamp {
+            return Err(Error::InvalidConsensusStateFromFuture {
+                current_timestamp: host_timestamp,
+                clock_drift: self.max_clock_drift,
+                consensus_state_timestamp: consensus_state.timestamp,
+            });
+        }
+        if let Some(processed_time) = consensus_state.processed_time {
+            let min_timestamp = host_timestamp
+                .as_unix_timestamp_nanos()
+                .saturating_sub(self.max_clock_drift.as_nanos());
+            if processed_time > max_timestamp
+                || processed_time.as_unix_timestamp_nanos() < min_timestamp
+            {
+                return Err(Error::InvalidConsensusStateProcessedTime {
+                    current_timestamp: host_timestamp,
+                    clock_drift: self.max_clock_drift,
+                    processed_time,
+                });
+            }
+        }
+        self.verify_within_trusting_period(consensus_state, host_timestamp)
+    }
 }
 
 pub fn canonicalize_client_state(client_state: ClientState) -> ClientState {
     let mut client_state = client_state;
     client_state.latest_height = Height::zero();
+    client_state.frozen_height = Height::zero();
     client_state
 }
 
-fn trim_left_zero(value: &[u8]) -> &[u8] {
-    let mut pos = 0;
-    for v in value {
-        if *v != 0 {
-            break;
+fn duration_from_parts(secs: u64, nanos: u32) -> Result<Duration, Error> {
+    if nanos >= 1_000_000_000 {
+        return Err(Error::InvalidDurationNanos(nanos));
+    }
+    Ok(Duration::new(secs, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_client_state_duration_conversion() {
+        let client_state = ClientState {
+            chain_id: U256::from(2018u64),
+            ibc_store_address: hex!("702e40245797c5a2108a566b3ce2bf14bc6af841"),
+            latest_height: Height::new(0, 100),
+            trusting_period: Duration::new(86400, 500_000_000),
+            max_clock_drift: Duration::from_millis(1500),
+            ..Default::default()
+        };
+        let raw = RawClientState::from(client_state.clone());
+        assert_eq!(raw.trusting_period, 86400);
+        assert_eq!(raw.trusting_period_nanos, 500_000_000);
+        assert_eq!(raw.max_clock_drift, 1);
+        assert_eq!(raw.max_clock_drift_nanos, 500_000_000);
+        assert_eq!(ClientState::try_from(raw.clone()).unwrap(), client_state);
+
+        let mut invalid = raw.clone();
+        invalid.max_clock_drift_nanos = 1_000_000_000;
+        assert!(matches!(
+            ClientState::try_from(invalid),
+            Err(Error::InvalidDurationNanos(1_000_000_000))
+        ));
+
+        // the chain id must fit in 256 bits
+        let mut invalid = raw.clone();
+        invalid.chain_id = vec![1u8; 33];
+        assert!(matches!(
+            ClientState::try_from(invalid),
+            Err(Error::InvalidClientStateChainIdSize(33))
+        ));
+        let mut padded = raw;
+        padded.chain_id = [vec![0u8], U256::from(2018u64).to_be_bytes_vec()].concat();
+        assert_eq!(ClientState::try_from(padded).unwrap(), client_state);
+    }
+
+    #[test]
+    fn test_client_state_validate() {
+        let client_state = ClientState {
+            chain_id: U256::from(2018u64),
+            ibc_store_address: hex!("702e40245797c5a2108a566b3ce2bf14bc6af841"),
+            latest_height: Height::new(0, 100),
+            ..Default::default()
+        };
+        assert!(client_state.validate().is_ok());
+
+        let mut invalid = client_state.clone();
+        invalid.chain_id = U256::ZERO;
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidClientStateZeroChainId)
+        ));
+
+        let mut invalid = client_state.clone();
+        invalid.latest_height = Height::zero();
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidClientStateZeroHeight)
+        ));
+
+        let mut invalid = client_state.clone();
+        invalid.ibc_store_address = Address::default();
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidClientStateZeroIbcStoreAddress)
+        ));
+
+        let mut invalid = client_state.clone();
+        invalid.frozen_height = Height::new(0, 1);
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidClientStateFrozen(_))
+        ));
+
+        let mut valid = client_state.clone();
+        valid.revision_schedule = vec![Height::new(1, 1), Height::new(3, 10)];
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.next_revision_height().unwrap(), Height::new(1, 1));
+        assert!(matches!(
+            client_state.next_revision_height(),
+            Err(Error::RevisionUpgradeNotScheduled(_))
+        ));
+        for revision_schedule in [
+            vec![Height::new(0, 200)],
+            vec![Height::new(1, 0)],
+            vec![Height::new(2, 1), Height::new(1, 1)],
+            vec![Height::new(1, 1), Height::new(1, 2)],
+        ] {
+            let mut invalid = client_state.clone();
+            invalid.revision_schedule = revision_schedule;
+            assert!(matches!(
+                invalid.validate(),
+                Err(Error::InvalidClientStateRevisionSchedule { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_initial_consensus_state() {
+        let client_state = ClientState {
+            trusting_period: Duration::from_secs(100),
+            max_clock_drift: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let time =
+            |secs: u64| Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap();
+        let consensus_state = |secs: u64| ConsensusState {
+            timestamp: time(secs),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: crate::consensus_state::ValidatorSet::Addresses(vec![[1u8; 20]]),
+            processed_time: None,
+        };
+        let host_timestamp = time(1000);
+
+        assert!(client_state
+            .validate_initial_consensus_state(&consensus_state(1010), host_timestamp)
+            .is_ok());
+        assert!(matches!(
+            client_state.validate_initial_consensus_state(&consensus_state(1011), host_timestamp),
+            Err(Error::InvalidConsensusStateFromFuture { .. })
+        ));
+        assert!(client_state
+            .validate_initial_consensus_state(&consensus_state(901), host_timestamp)
+            .is_ok());
+        assert!(matches!(
+            client_state.validate_initial_consensus_state(&consensus_state(900), host_timestamp),
+            Err(Error::OutOfTrustingPeriod { .. })
+        ));
+
+        // the processed time must be within the clock drift from the host timestamp
+        let mut consensus_state = consensus_state(995);
+        for secs in [990, 1000, 1010] {
+            consensus_state.processed_time = Some(time(secs));
+            assert!(client_state
+                .validate_initial_consensus_state(&consensus_state, host_timestamp)
+                .is_ok());
         }
-        pos += 1;
+        for secs in [989, 1011] {
+            consensus_state.processed_time = Some(time(secs));
+            assert!(matches!(
+                client_state.validate_initial_consensus_state(&consensus_state, host_timestamp),
+                Err(Error::InvalidConsensusStateProcessedTime { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_verify_delay_passed() {
+        let mut client_state = ClientState::default();
+        let time =
+            |secs: u64| Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap();
+        let mut consensus_state = ConsensusState {
+            timestamp: time(1000),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: crate::consensus_state::ValidatorSet::Addresses(vec![[1u8; 20]]),
+            processed_time: Some(time(1010)),
+        };
+        assert!(client_state
+            .verify_delay_passed(&consensus_state, time(1010), Duration::ZERO)
+            .is_ok());
+
+        client_state.delay_time_period = Duration::from_secs(10);
+        assert!(matches!(
+            client_state.verify_delay_passed(&consensus_state, time(1019), Duration::ZERO),
+            Err(Error::DelayPeriodNotPassed { .. })
+        ));
+        assert!(client_state
+            .verify_delay_passed(&consensus_state, time(1020), Duration::ZERO)
+            .is_ok());
+
+        // the longer delay period of the verification is applied
+        assert!(matches!(
+            client_state.verify_delay_passed(&consensus_state, time(1029), Duration::from_secs(20)),
+            Err(Error::DelayPeriodNotPassed { .. })
+        ));
+        assert!(client_state
+            .verify_delay_passed(&consensus_state, time(1030), Duration::from_secs(20))
+            .is_ok());
+        assert!(client_state
+            .verify_delay_passed(&consensus_state, time(1020), Duration::from_secs(5))
+            .is_ok());
+
+        // the delay period cannot be verified without the processed time
+        consensus_state.processed_time = None;
+        assert!(matches!(
+            client_state.verify_delay_passed(&consensus_state, time(2000), Duration::ZERO),
+            Err(Error::ProcessedTimeNotFound)
+        ));
+        client_state.delay_time_period = Duration::ZERO;
+        assert!(client_state
+            .verify_delay_passed(&consensus_state, time(1010), Duration::ZERO)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_client_state_validate_clock_drift() {
+        let mut client_state = ClientState {
+            chain_id: U256::from(2018u64),
+            ibc_store_address: hex!("702e40245797c5a2108a566b3ce2bf14bc6af841"),
+            latest_height: Height::new(0, 100),
+            trusting_period: Duration::from_secs(10),
+            max_clock_drift: Duration::from_millis(9999),
+            ..Default::default()
+        };
+        assert!(client_state.validate().is_ok());
+
+        client_state.max_clock_drift = Duration::from_secs(10);
+        assert!(matches!(
+            client_state.validate(),
+            Err(Error::InvalidClientStateMaxClockDriftNotLessThanTrustingPeriod { .. })
+        ));
+
+        // the trusting period check is disabled
+        client_state.trusting_period = Duration::ZERO;
+        assert!(client_state.validate().is_ok());
+    }
+
+    #[test]
+    fn test_header_timestamp() {
+        let mut client_state = ClientState::default();
+        assert_eq!(
+            client_state
+                .header_timestamp(U256::from(1_700_000_000u64))
+                .unwrap(),
+            Time::from_unix_timestamp_nanos(1_700_000_000_000_000_000).unwrap()
+        );
+        let overflow = u128::MAX / 1_000_000_000 + 1;
+        assert!(matches!(
+            client_state.header_timestamp(U256::from(overflow)),
+            Err(Error::HeaderTimestampOverflow(timestamp)) if timestamp == overflow
+        ));
+        assert!(matches!(
+            client_state.header_timestamp(U256::from(u128::MAX) + U256::from(1u64)),
+            Err(Error::FromUint128Error(_))
+        ));
+
+        client_state.timestamp_precision = TimestampPrecision::Milliseconds;
+        assert_eq!(
+            client_state
+                .header_timestamp(U256::from(1_700_000_000_123u64))
+                .unwrap(),
+            Time::from_unix_timestamp_nanos(1_700_000_000_123_000_000).unwrap()
+        );
+        let overflow = u128::MAX / 1_000_000 + 1;
+        assert!(matches!(
+            client_state.header_timestamp(U256::from(overflow)),
+            Err(Error::HeaderTimestampOverflow(timestamp)) if timestamp == overflow
+        ));
+        assert!(matches!(
+            client_state.header_timestamp(U256::from(u128::MAX) + U256::from(1u64)),
+            Err(Error::FromUint128Error(_))
+        ));
     }
-    &value[pos..]
 }
@@ -1,19 +1,29 @@
 use crate::errors::Error;
 use crate::internal_prelude::*;
 use crate::types::{Address, H256, U256};
+use alloc::collections::BTreeMap;
 use hex_literal::hex;
 use libsecp256k1::{curve::Scalar, Message, PublicKey, RecoveryId, Signature};
+use light_client::types::Height;
 use rlp::Rlp;
+use sha2::{Digest, Sha256};
 use tiny_keccak::Keccak;
 
-/// decode rlp format `List<List>` to `Vec<List>`
+/// decode rlp format `List<List>` or `List<Bytes>` to `Vec<List>`
+///
+/// each element is either a trie node (`List`) or an RLP encoded trie node (`Bytes`).
+/// the latter is used by the multiproof format, which contains a deduplicated set of trie nodes for several keys.
 pub fn decode_eip1184_rlp_proof(proof: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
     let r = Rlp::new(proof);
     if r.is_list() {
         Ok(r.into_iter()
             .map(|r| {
-                let elems: Vec<Vec<u8>> = r.as_list()?;
-                Ok(rlp::encode_list::<Vec<u8>, Vec<u8>>(&elems).into())
+                if r.is_list() {
+                    let elems: Vec<Vec<u8>> = r.as_list()?;
+                    Ok(rlp::encode_list::<Vec<u8>, Vec<u8>>(&elems).into())
+                } else {
+                    Ok(r.data()?.to_vec())
+                }
             })
             .collect::<Result<Vec<Vec<u8>>, Error>>()?)
     } else {
@@ -21,6 +31,162 @@ pub fn decode_eip1184_rlp_proof(proof: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
     }
 }
 
+/// keccak256 hash of the RLP encoded empty string, which is the root of the empty trie
+const EMPTY_TRIE_ROOT: [u8; 32] =
+    hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+
+/// trie nodes of a proof indexed by their hashes
+///
+/// the proof is decoded once and the values of several keys are looked up against the same nodes,
+/// so the nodes shared by the paths of the keys are neither copied nor decoded again
+#[derive(Clone, Debug, Default)]
+pub struct ProofNodes {
+    nodes: BTreeMap<[u8; 32], Vec<u8>>,
+}
+
+impl ProofNodes {
+    /// decodes the proof in the format of `decode_eip1184_rlp_proof`
+    pub fn decode(proof: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            nodes: decode_eip1184_rlp_proof(proof)?
+                .into_iter()
+                .map(|node| (keccak256(&node), node))
+                .collect(),
+        })
+    }
+
+    /// returns the value of `key` in the trie of `root`, or `None` if the nodes prove its absence
+    ///
+    /// the key is hashed with keccak256 as the state and storage tries of Ethereum
+    pub fn get(&self, root: H256, key: &[u8]) -> Result<Option<&[u8]>, Error> {
+        let root: [u8; 32] = root.to_be_bytes();
+        if root == EMPTY_TRIE_ROOT {
+            return Ok(None);
+        }
+        let nibbles = to_nibbles(&keccak256(key));
+        let mut rest = nibbles.as_slice();
+        let mut node = self.node(&root)?;
+        loop {
+            let r = Rlp::new(node);
+            let child = match r.item_count()? {
+                17 => match rest.split_first() {
+                    Some((nibble, tail)) => {
+                        rest = tail;
+                        r.at(*nibble as usize)?
+                    }
+                    None => {
+                        let value = r.at(16)?.data()?;
+                        return Ok((!value.is_empty()).then_some(value));
+                    }
+                },
+                2 => {
+                    let (path, is_leaf) = decode_hex_prefix(r.at(0)?.data()?)
+                        .ok_or_else(|| Error::InvalidTrieNode(node.to_vec()))?;
+                    if is_leaf {
+                        return if rest == path.as_slice() {
+                            Ok(Some(r.at(1)?.data()?))
+                        } else {
+                            Ok(None)
+                        };
+                    }
+                    match rest.strip_prefix(path.as_slice()) {
+                        Some(tail) => rest = tail,
+                        None => return Ok(None),
+                    }
+                    r.at(1)?
+                }
+                _ => return Err(Error::InvalidTrieNode(node.to_vec())),
+            };
+            node = if child.is_list() {
+                // the child node is embedded because its encoding is shorter than 32 bytes
+                child.as_raw()
+            } else {
+                let hash = child.data()?;
+                if hash.is_empty() {
+                    return Ok(None);
+                }
+                self.node(hash)?
+            };
+        }
+    }
+
+    fn node(&self, hash: &[u8]) -> Result<&[u8], Error> {
+        let hash: [u8; 32] = hash
+            .try_into()
+            .map_err(|_| Error::InvalidTrieNodeReference(hash.to_vec()))?;
+        self.nodes
+            .get(&hash)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::TrieNodeNotFound(H256::from_be_bytes(hash)))
+    }
+}
+
+fn to_nibbles(bz: &[u8]) -> Vec<u8> {
+    bz.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// decodes the hex prefix encoded path of the leaf or extension node into the nibbles and whether the node is a leaf
+fn decode_hex_prefix(bz: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let (first, rest) = bz.split_first()?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return None;
+    }
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    } else if first & 0x0f != 0 {
+        return None;
+    }
+    nibbles.extend(to_nibbles(rest));
+    Some((nibbles, flag & 2 == 2))
+}
+
+/// encode the proofs into the multiproof format, which can be decoded by `decode_eip1184_rlp_proof`
+///
+/// each proof is a list of RLP encoded trie nodes like `accountProof` and `storageProof` of `eth_getProof`.
+/// the nodes shared by the proofs are included only once
+pub fn encode_eip1184_rlp_multiproof(proofs: &[Vec<Vec<u8>>]) -> Vec<u8> {
+    let mut nodes: Vec<&Vec<u8>> = Vec::new();
+    for node in proofs.iter().flatten() {
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    let mut stream = rlp::RlpStream::new_list(nodes.len());
+    for node in nodes {
+        stream.append(node);
+    }
+    stream.out().to_vec()
+}
+
+/// calculate the storage value of the IBC commitment for `value`, which is the RLP encoded `keccak256(value)` without leading zeros
+pub fn calculate_ibc_commitment_storage_value(value: &[u8]) -> Vec<u8> {
+    rlp::encode(&trim_left_zero(keccak256(value).as_slice())).to_vec()
+}
+
+/// calculate the commitment of the packet, which is the value of `verify_membership` for the packet commitment path
+///
+/// it is `sha256(timeout_timestamp || timeout_height.revision_number || timeout_height.revision_height || sha256(data))` as in ibc-go
+pub fn packet_commitment(data: &[u8], timeout_height: Height, timeout_timestamp: u64) -> [u8; 32] {
+    let mut bz = Vec::with_capacity(56);
+    bz.extend_from_slice(&timeout_timestamp.to_be_bytes());
+    bz.extend_from_slice(&timeout_height.revision_number().to_be_bytes());
+    bz.extend_from_slice(&timeout_height.revision_height().to_be_bytes());
+    bz.extend_from_slice(&sha256(data));
+    sha256(&bz)
+}
+
+/// calculate the commitment of the acknowledgement, which is the value of `verify_membership` for the packet acknowledgement path
+pub fn packet_acknowledgement_commitment(acknowledgement: &[u8]) -> [u8; 32] {
+    sha256(acknowledgement)
+}
+
+/// returns the value of the packet receipt, which is the value of `verify_membership` for the packet receipt path
+pub fn packet_receipt_commitment() -> Vec<u8> {
+    vec![1]
+}
+
 pub fn calculate_ibc_commitment_storage_key(path: &[u8]) -> U256 {
     let h = keccak256(
         &[
@@ -32,6 +198,34 @@ pub fn calculate_ibc_commitment_storage_key(path: &[u8]) -> U256 {
     U256::from_be_slice(&h)
 }
 
+/// calculate the commitment to the results of a batch membership verification
+///
+/// it is the keccak256 hash of `keccak256(prefix)` followed by `keccak256(path) || value_commitment` for each item,
+/// where `value_commitment` is `keccak256(value)` for a membership and 32 zero bytes for a non-membership
+pub fn calculate_memberships_commitment(
+    prefix: &[u8],
+    items: &[(String, Option<Vec<u8>>)],
+) -> H256 {
+    let mut bz = Vec::with_capacity(32 + items.len() * 64);
+    bz.extend_from_slice(&keccak256(prefix));
+    for (path, value) in items {
+        bz.extend_from_slice(&keccak256(path.as_bytes()));
+        bz.extend_from_slice(&value.as_ref().map_or([0u8; 32], |v| keccak256(v)));
+    }
+    H256::from_be_bytes(keccak256(&bz))
+}
+
+/// calculate the hash of the validator set, which does not depend on the order of `validators`
+pub fn calculate_validators_hash(validators: &[Address]) -> H256 {
+    let mut validators = validators.to_vec();
+    validators.sort();
+    H256::from_be_bytes(keccak256(&validators.concat()))
+}
+
+pub fn sha256(bz: &[u8]) -> [u8; 32] {
+    Sha256::digest(bz).into()
+}
+
 pub fn keccak256(bz: &[u8]) -> [u8; 32] {
     let mut keccak = Keccak::new_keccak256();
     let mut result = [0u8; 32];
@@ -40,16 +234,52 @@ pub fn keccak256(bz: &[u8]) -> [u8; 32] {
     result
 }
 
+/// recovers the address of the signer of `sign_hash` from the 65 bytes signature `r || s || v`
+///
+/// the signature is parsed by `parse_signature` before the recovery
 pub fn verify_signature(sign_hash: H256, signature: &[u8]) -> Result<Address, Error> {
+    let (sig, rid) = parse_signature(signature)?;
+    recover_address(sign_hash, &sig, &rid)
+}
+
+/// parses the 65 bytes signature `r || s || v` strictly as Besu does: `r` and `s` must be in `[1, n)`, `s` must not be greater than `n/2`,
+/// and `v` must be the recovery id as either 0/1 or 27/28
+pub fn parse_signature(signature: &[u8]) -> Result<(Signature, RecoveryId), Error> {
     if signature.len() != 65 {
         return Err(Error::InvalidSignatureLength(signature.len()));
     }
+    let parse_scalar = |bz: &[u8]| -> Option<Scalar> {
+        let mut scalar = Scalar::default();
+        // unwrap is safe because the length is 32
+        let overflow: bool = scalar.set_b32(bz.try_into().unwrap()).into();
+        (!overflow && !scalar.is_zero()).then_some(scalar)
+    };
+    let r = parse_scalar(&signature[..32])
+        .ok_or_else(|| Error::InvalidSignatureR(signature[..32].to_vec()))?;
+    let s = parse_scalar(&signature[32..64])
+        .ok_or_else(|| Error::InvalidSignatureS(signature[32..64].to_vec()))?;
+    if s.is_high() {
+        return Err(Error::InvalidSignatureHighS(signature[32..64].to_vec()));
+    }
+    let v = match signature[64] {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        v => return Err(Error::InvalidSignatureRecoveryId(v)),
+    };
+    Ok((Signature { r, s }, RecoveryId::parse(v)?))
+}
+
+/// recovers the address of the signer of `sign_hash` from the signature parsed by `parse_signature`
+///
+/// it fails if `r` is not the x-coordinate of a point on the curve, which cannot be known without the recovery
+pub fn recover_address(
+    sign_hash: H256,
+    signature: &Signature,
+    recovery_id: &RecoveryId,
+) -> Result<Address, Error> {
     let mut s = Scalar::default();
     let _ = s.set_b32(&sign_hash.to_be_bytes());
-
-    let sig = Signature::parse_overflowing_slice(&signature[..64])?;
-    let rid = RecoveryId::parse(signature[64])?;
-    let signer: PublicKey = libsecp256k1::recover(&Message(s), &sig, &rid)?;
+    let signer: PublicKey = libsecp256k1::recover(&Message(s), signature, recovery_id)?;
     Ok(address_from_pubkey(&signer))
 }
 
@@ -59,3 +289,141 @@ pub fn address_from_pubkey(pubkey: &PublicKey) -> Address {
     address.copy_from_slice(&hash[12..]);
     address
 }
+
+fn trim_left_zero(value: &[u8]) -> &[u8] {
+    let mut pos = 0;
+    for v in value {
+        if *v != 0 {
+            break;
+        }
+        pos += 1;
+    }
+    &value[pos..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_commitments() {
+        assert_eq!(
+            packet_commitment(b"hello", Height::new(1, 100), 1700000000000000000),
+            hex!("512d19e8d8bdadc538c6e1e13e11b7019aa57be7d15c9c15871e02a51dfd1125")
+        );
+        assert_eq!(
+            packet_acknowledgement_commitment(b"ack"),
+            hex!("64a37929fb113e18daa6263a1fb1f90c51d262552efa5a50596f5f653ba955f8")
+        );
+        assert_eq!(packet_receipt_commitment(), vec![1]);
+    }
+
+    #[test]
+    fn test_calculate_ibc_commitment_storage_value() {
+        let value = packet_acknowledgement_commitment(b"ack");
+        let trimmed: Vec<u8> = keccak256(&value)
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        assert_eq!(
+            calculate_ibc_commitment_storage_value(&value),
+            rlp::encode(&trimmed).to_vec()
+        );
+        assert_eq!(calculate_ibc_commitment_storage_value(&value)[1..], trimmed);
+    }
+
+    #[test]
+    fn test_decode_eip1184_rlp_multiproof() {
+        let nodes = [
+            rlp::encode_list::<Vec<u8>, Vec<u8>>(&[vec![0x20, 0x01], vec![0x02; 40]]).to_vec(),
+            rlp::encode_list::<Vec<u8>, Vec<u8>>(&[vec![0x30], vec![0x03; 32]]).to_vec(),
+            rlp::encode_list::<Vec<u8>, Vec<u8>>(&[vec![0x31], vec![0x04; 32]]).to_vec(),
+        ];
+
+        let mut stream = rlp::RlpStream::new_list(2);
+        stream.append_raw(&nodes[0], 1);
+        stream.append_raw(&nodes[1], 1);
+        let proof = stream.out().to_vec();
+        assert_eq!(
+            decode_eip1184_rlp_proof(&proof).unwrap(),
+            vec![nodes[0].clone(), nodes[1].clone()]
+        );
+
+        let multiproof = encode_eip1184_rlp_multiproof(&[
+            vec![nodes[0].clone(), nodes[1].clone()],
+            vec![nodes[0].clone(), nodes[2].clone()],
+        ]);
+        assert_eq!(
+            decode_eip1184_rlp_proof(&multiproof).unwrap(),
+            nodes.to_vec()
+        );
+
+        assert!(matches!(
+            decode_eip1184_rlp_proof(&rlp::encode(&vec![0x01u8; 8])),
+            Err(Error::InvalidRLPFormatNotList(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_strict() {
+        use crate::test_utils::qbft::QbftValidator;
+
+        // order of the secp256k1 curve
+        let n = U256::from_be_bytes(hex!(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+        ));
+        let validator = QbftValidator::from_seed(0);
+        let hash = H256::from_be_bytes([1u8; 32]);
+        let seal = validator.sign(hash);
+        assert_eq!(verify_signature(hash, &seal).unwrap(), validator.address);
+
+        // the recovery id is normalized
+        let mut sig = seal.clone();
+        sig[64] += 27;
+        assert_eq!(verify_signature(hash, &sig).unwrap(), validator.address);
+        for v in [2, 3, 26, 29] {
+            let mut sig = seal.clone();
+            sig[64] = v;
+            assert!(matches!(
+                verify_signature(hash, &sig),
+                Err(Error::InvalidSignatureRecoveryId(_))
+            ));
+        }
+
+        // r and s must be in [1, n)
+        for (range, value) in [
+            (0..32, U256::ZERO),
+            (0..32, n),
+            (32..64, U256::ZERO),
+            (32..64, n),
+        ] {
+            let mut sig = seal.clone();
+            sig[range.clone()].copy_from_slice(&value.to_be_bytes::<32>());
+            let res = verify_signature(hash, &sig);
+            if range.start == 0 {
+                assert!(matches!(res, Err(Error::InvalidSignatureR(_))), "{:?}", res);
+            } else {
+                assert!(matches!(res, Err(Error::InvalidSignatureS(_))), "{:?}", res);
+            }
+        }
+
+        // the high-S signature is rejected even though it recovers the same signer with the flipped recovery id
+        let s = U256::from_be_slice(&seal[32..64]);
+        let mut sig = seal.clone();
+        sig[32..64].copy_from_slice(&(n - s).to_be_bytes::<32>());
+        sig[64] ^= 1;
+        assert!(matches!(
+            verify_signature(hash, &sig),
+            Err(Error::InvalidSignatureHighS(_))
+        ));
+
+        // r is well-formed but not the x-coordinate of a point on the curve, so only the recovery fails
+        let mut sig = seal.clone();
+        sig[..32].copy_from_slice(&U256::from(5u64).to_be_bytes::<32>());
+        assert!(parse_signature(&sig).is_ok());
+        assert!(matches!(
+            verify_signature(hash, &sig),
+            Err(Error::Secp256k1(_))
+        ));
+    }
+}
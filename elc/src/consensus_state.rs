@@ -1,3 +1,4 @@
+use crate::commitment::calculate_validators_hash;
 use crate::errors::Error;
 use crate::internal_prelude::*;
 use crate::types::{Address, H256};
@@ -12,16 +13,78 @@ pub const BESU_QBFT_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.qbft.v1.
 pub struct ConsensusState {
     pub timestamp: Time,
     pub root: H256,
-    pub validators: Vec<Address>,
+    pub validators: ValidatorSet,
+    /// host timestamp at which the consensus state was processed
+    pub processed_time: Option<Time>,
 }
 
-impl From<ConsensusState> for RawConsensusState {
-    fn from(value: ConsensusState) -> Self {
-        RawConsensusState {
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidatorSet {
+    /// validator addresses of the block
+    Addresses(Vec<Address>),
+    /// keccak256 hash of the validator addresses sorted in ascending order
+    Hash(H256),
+}
+
+impl ValidatorSet {
+    pub fn new(validators: Vec<Address>, use_hash: bool) -> Self {
+        if use_hash {
+            ValidatorSet::Hash(calculate_validators_hash(&validators))
+        } else {
+            ValidatorSet::Addresses(validators)
+        }
+    }
+
+    /// returns the hash of the validator set
+    pub fn hash(&self) -> H256 {
+        match self {
+            ValidatorSet::Addresses(validators) => calculate_validators_hash(validators),
+            ValidatorSet::Hash(hash) => *hash,
+        }
+    }
+
+    /// returns the validator addresses of the set
+    ///
+    /// if the set is represented by its hash, `witness` must be the validators corresponding to the hash
+    pub fn resolve(&self, witness: &[Address]) -> Result<Vec<Address>, Error> {
+        match self {
+            ValidatorSet::Addresses(validators) => Ok(validators.clone()),
+            ValidatorSet::Hash(hash) => {
+                let actual = calculate_validators_hash(witness);
+                if actual != *hash {
+                    return Err(Error::TrustedValidatorsHashMismatch {
+                        expected: *hash,
+                        actual,
+                    });
+                }
+                Ok(witness.to_vec())
+            }
+        }
+    }
+}
+
+impl TryFrom<ConsensusState> for RawConsensusState {
+    type Error = Error;
+
+    fn try_from(value: ConsensusState) -> Result<Self, Self::Error> {
+        let (validators, validators_hash) = match value.validators {
+            ValidatorSet::Addresses(validators) => {
+                (validators.iter().map(|v| v.to_vec()).collect(), vec![])
+            }
+            ValidatorSet::Hash(hash) => (vec![], hash.to_be_bytes_vec()),
+        };
+        Ok(RawConsensusState {
             timestamp: value.timestamp.as_unix_timestamp_secs(),
+            timestamp_nanos: (value.timestamp.as_unix_timestamp_nanos() % 1_000_000_000) as u32,
             root: value.root.to_be_bytes_vec(),
-            validators: value.validators.iter().map(|v| v.to_vec()).collect(),
-        }
+            validators,
+            validators_hash,
+            processed_time: match value.processed_time {
+                Some(t) => u64::try_from(t.as_unix_timestamp_nanos())
+                    .map_err(|_| Error::InvalidConsensusStateProcessedTimeOverflow(t))?,
+                None => 0,
+            },
+        })
     }
 }
 
@@ -30,18 +93,44 @@ impl TryFrom<RawConsensusState> for ConsensusState {
 
     fn try_from(value: RawConsensusState) -> Result<Self, Self::Error> {
         Ok(ConsensusState {
-            timestamp: Time::from_unix_timestamp_nanos(value.timestamp as u128 * 1_000_000_000)?,
+            timestamp: {
+                if value.timestamp_nanos >= 1_000_000_000 {
+                    return Err(Error::InvalidConsensusStateTimestampNanos(
+                        value.timestamp_nanos,
+                    ));
+                }
+                Time::from_unix_timestamp_nanos(
+                    value.timestamp as u128 * 1_000_000_000 + value.timestamp_nanos as u128,
+                )?
+            },
             root: H256::try_from_be_slice(&value.root)
                 .ok_or_else(|| Error::InvalidConsensusStateRootSize(value.root.len()))?,
-            validators: value
-                .validators
-                .iter()
-                .map(|v| {
-                    v.as_slice()
-                        .try_into()
-                        .map_err(Error::SliceToArrayConversionError)
-                })
-                .collect::<Result<_, _>>()?,
+            validators: if value.validators_hash.is_empty() {
+                ValidatorSet::Addresses(
+                    value
+                        .validators
+                        .iter()
+                        .map(|v| {
+                            v.as_slice()
+                                .try_into()
+                                .map_err(Error::SliceToArrayConversionError)
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            } else if value.validators.is_empty() {
+                ValidatorSet::Hash(H256::try_from_be_slice(&value.validators_hash).ok_or_else(
+                    || Error::InvalidConsensusStateValidatorsHashSize(value.validators_hash.len()),
+                )?)
+            } else {
+                return Err(Error::InvalidConsensusStateBothValidatorsAndValidatorsHash);
+            },
+            processed_time: if value.processed_time == 0 {
+                None
+            } else {
+                Some(Time::from_unix_timestamp_nanos(
+                    value.processed_time as u128,
+                )?)
+            },
         })
     }
 }
@@ -59,21 +148,178 @@ impl TryFrom<Any> for ConsensusState {
     }
 }
 
-impl From<ConsensusState> for Any {
-    fn from(value: ConsensusState) -> Self {
-        ProtoAny {
+impl TryFrom<ConsensusState> for Any {
+    type Error = Error;
+
+    fn try_from(value: ConsensusState) -> Result<Self, Self::Error> {
+        Ok(ProtoAny {
             type_url: BESU_QBFT_CONSENSUS_STATE_TYPE_URL.to_string(),
-            value: RawConsensusState::from(value).encode_to_vec(),
+            value: RawConsensusState::try_from(value)?.encode_to_vec(),
         }
-        .into()
+        .into())
     }
 }
 
+pub fn canonicalize_consensus_state(consensus_state: ConsensusState) -> ConsensusState {
+    let mut consensus_state = consensus_state;
+    consensus_state.processed_time = None;
+    consensus_state
+}
+
 impl ConsensusState {
+    /// returns true if both consensus states commit to the same block state
+    ///
+    /// the validator sets are compared by their hashes, so the representation of the sets does not matter.
+    /// `processed_time` is not compared because it depends on the host
+    pub fn is_equivalent(&self, other: &ConsensusState) -> bool {
+        self.timestamp == other.timestamp
+            && self.root == other.root
+            && self.validators.hash() == other.validators.hash()
+    }
+
     pub fn validate(&self) -> Result<(), Error> {
+        if self.timestamp.as_unix_timestamp_nanos() == 0 {
+            return Err(Error::InvalidConsensusStateZeroTimestamp);
+        }
         if self.root.as_uint().is_zero() {
             return Err(Error::InvalidConsensusStateZeroRoot);
         }
+        match &self.validators {
+            ValidatorSet::Addresses(validators) => {
+                if validators.is_empty() {
+                    return Err(Error::InvalidConsensusStateEmptyValidators);
+                }
+                for (i, validator) in validators.iter().enumerate() {
+                    if validators[..i].contains(validator) {
+                        return Err(Error::InvalidConsensusStateDuplicateValidator(*validator));
+                    }
+                }
+            }
+            ValidatorSet::Hash(hash) => {
+                if hash.as_uint().is_zero() {
+                    return Err(Error::InvalidConsensusStateZeroValidatorsHash);
+                }
+            }
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_validator_set() {
+        let validators = vec![
+            hex!("ee3353e587cfa91625a1adaef308a726de3803d3"),
+            hex!("647bfdd19655e51e69d35454ff3a92f8828e6302"),
+            hex!("b92e91f4dcc9d28503be521afa2a8fbf3c1acf60"),
+            hex!("a5c8416b9d13417b45b45ada76408f39d1e504ef"),
+        ];
+        let set = ValidatorSet::new(validators.clone(), false);
+        assert_eq!(set.resolve(&[]).unwrap(), validators);
+
+        let set = ValidatorSet::new(validators.clone(), true);
+        assert!(set.resolve(&[]).is_err());
+        assert!(set.resolve(&validators[1..]).is_err());
+        assert_eq!(set.resolve(&validators).unwrap(), validators);
+        let mut reordered = validators.clone();
+        reordered.reverse();
+        assert_eq!(set.resolve(&reordered).unwrap(), reordered);
+    }
+
+    #[test]
+    fn test_consensus_state_validate() {
+        let consensus_state = ConsensusState {
+            timestamp: Time::from_unix_timestamp_nanos(1715495307_000_000_000).unwrap(),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: ValidatorSet::Addresses(vec![
+                hex!("647bfdd19655e51e69d35454ff3a92f8828e6302"),
+                hex!("a5c8416b9d13417b45b45ada76408f39d1e504ef"),
+            ]),
+            processed_time: None,
+        };
+        assert!(consensus_state.validate().is_ok());
+
+        let mut invalid = consensus_state.clone();
+        invalid.timestamp = Time::from_unix_timestamp_nanos(0).unwrap();
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidConsensusStateZeroTimestamp)
+        ));
+
+        let mut invalid = consensus_state.clone();
+        invalid.root = H256::ZERO;
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidConsensusStateZeroRoot)
+        ));
+
+        let mut invalid = consensus_state.clone();
+        invalid.validators = ValidatorSet::Addresses(vec![]);
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidConsensusStateEmptyValidators)
+        ));
+
+        let mut invalid = consensus_state.clone();
+        invalid.validators = ValidatorSet::Addresses(vec![[1u8; 20], [2u8; 20], [1u8; 20]]);
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidConsensusStateDuplicateValidator(v)) if v == [1u8; 20]
+        ));
+
+        let mut invalid = consensus_state;
+        invalid.validators = ValidatorSet::Hash(H256::ZERO);
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidConsensusStateZeroValidatorsHash)
+        ));
+    }
+
+    #[test]
+    fn test_consensus_state_validators_encoding() {
+        let validators = vec![
+            hex!("647bfdd19655e51e69d35454ff3a92f8828e6302"),
+            hex!("a5c8416b9d13417b45b45ada76408f39d1e504ef"),
+        ];
+        for use_hash in [false, true] {
+            let cs = ConsensusState {
+                timestamp: Time::from_unix_timestamp_nanos(1715495307_123_456_789).unwrap(),
+                root: H256::from_be_bytes([1u8; 32]),
+                validators: ValidatorSet::new(validators.clone(), use_hash),
+                processed_time: Some(
+                    Time::from_unix_timestamp_nanos(1715495310_000_000_000).unwrap(),
+                ),
+            };
+            let raw = RawConsensusState::try_from(cs.clone()).unwrap();
+            assert_eq!(raw.validators.is_empty(), use_hash);
+            assert_eq!(raw.validators_hash.is_empty(), !use_hash);
+            assert_eq!(ConsensusState::try_from(raw).unwrap(), cs);
+        }
+
+        let raw = RawConsensusState {
+            timestamp: 1715495307,
+            root: [1u8; 32].to_vec(),
+            validators: validators.iter().map(|v| v.to_vec()).collect(),
+            validators_hash: [2u8; 32].to_vec(),
+            timestamp_nanos: 0,
+            processed_time: 0,
+        };
+        assert!(ConsensusState::try_from(raw).is_err());
+
+        // the processed time must fit in u64 nanoseconds
+        let cs = ConsensusState {
+            timestamp: Time::from_unix_timestamp_nanos(1715495307_000_000_000).unwrap(),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: ValidatorSet::Addresses(validators),
+            processed_time: Some(Time::from_unix_timestamp_nanos(u64::MAX as u128 + 1).unwrap()),
+        };
+        assert!(matches!(
+            RawConsensusState::try_from(cs),
+            Err(Error::InvalidConsensusStateProcessedTimeOverflow(_))
+        ));
+    }
+}
@@ -2,24 +2,93 @@ use core::time::Duration;
 
 use crate::{
     internal_prelude::*,
-    types::{Address, H256},
+    seal::CommitSealsReport,
+    types::{Address, H256, U256},
 };
 use displaydoc::Display;
-use light_client::{types::Time, LightClientSpecificError};
+use light_client::{
+    types::{Height, Time},
+    LightClientSpecificError,
+};
 
 #[derive(Debug, Display)]
 pub enum Error {
+    /// invalid client state: chain id is zero
+    InvalidClientStateZeroChainId,
+    /// invalid client state: chain id does not fit in 256 bits: size={0}
+    InvalidClientStateChainIdSize(usize),
     /// invalid client state: height is zero
     InvalidClientStateZeroHeight,
     /// invalid client state: ibc store address is zero
     InvalidClientStateZeroIbcStoreAddress,
+    /// invalid client state: max clock drift must be less than trusting period: max_clock_drift={max_clock_drift:?} trusting_period={trusting_period:?}
+    InvalidClientStateMaxClockDriftNotLessThanTrustingPeriod {
+        max_clock_drift: Duration,
+        trusting_period: Duration,
+    },
+    /// invalid client state: client is frozen: frozen_height={0:?}
+    InvalidClientStateFrozen(Height),
+    /// invalid client state: unknown timestamp precision: {0}
+    InvalidClientStateTimestampPrecision(i32),
+    /// invalid client state: revision schedule must have the heights of the revisions after the latest height in ascending order: latest_height={latest_height:?} revision_schedule={revision_schedule:?}
+    InvalidClientStateRevisionSchedule {
+        latest_height: Height,
+        revision_schedule: Vec<Height>,
+    },
+    /// invalid consensus state: timestamp is zero
+    InvalidConsensusStateZeroTimestamp,
     /// invalid consensus state: state root is zero
     InvalidConsensusStateZeroRoot,
+    /// invalid consensus state: validators are empty
+    InvalidConsensusStateEmptyValidators,
+    /// invalid consensus state: duplicate validator: {0:?}
+    InvalidConsensusStateDuplicateValidator(Address),
+    /// invalid consensus state: validators hash is zero
+    InvalidConsensusStateZeroValidatorsHash,
+    /// invalid consensus state: consensus state is coming from future: current_timestamp={current_timestamp} clock_drift={clock_drift:?} consensus_state_timestamp={consensus_state_timestamp}
+    InvalidConsensusStateFromFuture {
+        current_timestamp: Time,
+        clock_drift: Duration,
+        consensus_state_timestamp: Time,
+    },
+    /// invalid consensus state: processed time is not within the clock drift from the host timestamp: current_timestamp={current_timestamp} clock_drift={clock_drift:?} processed_time={processed_time}
+    InvalidConsensusStateProcessedTime {
+        current_timestamp: Time,
+        clock_drift: Duration,
+        processed_time: Time,
+    },
+    /// invalid consensus state: processed time overflows u64 nanoseconds: {0}
+    InvalidConsensusStateProcessedTimeOverflow(Time),
     /// invalid consensus state: state root size is not 32 but {0}
     InvalidConsensusStateRootSize(usize),
+    /// invalid consensus state: timestamp nanos must be less than 1e9: {0}
+    InvalidConsensusStateTimestampNanos(u32),
+    /// invalid consensus state: validators hash size is not 32 but {0}
+    InvalidConsensusStateValidatorsHashSize(usize),
+    /// invalid consensus state: both validators and validators hash are set
+    InvalidConsensusStateBothValidatorsAndValidatorsHash,
 
     /// invalid header: trusted height is zero
     InvalidHeaderZeroTrustedHeight,
+    /// invalid checkpoint: block hash size is not 32 but {0}
+    InvalidCheckpointBlockHashSize(usize),
+    /// checkpoint block hash mismatch: expected={expected:?} actual={actual:?}
+    CheckpointBlockHashMismatch { expected: H256, actual: H256 },
+    /// checkpoint height mismatch: expected={expected} actual={actual}
+    CheckpointHeightMismatch { expected: u64, actual: u64 },
+    /// invalid revision upgrade: header is empty
+    InvalidRevisionUpgradeEmptyHeader,
+    /// revision upgrade must be trusted by a consensus state of the latest revision: latest_height={latest_height:?} trusted_height={trusted_height:?}
+    RevisionUpgradeTrustedHeightNotInLatestRevision {
+        latest_height: Height,
+        trusted_height: Height,
+    },
+    /// revision upgrade is not scheduled for the next revision: latest_height={0:?}
+    RevisionUpgradeNotScheduled(Height),
+    /// revision upgrade height mismatch: expected={expected:?} actual={actual:?}
+    RevisionUpgradeHeightMismatch { expected: Height, actual: Height },
+    /// trusted validators hash mismatch: expected={expected:?} actual={actual:?}
+    TrustedValidatorsHashMismatch { expected: H256, actual: H256 },
 
     /// invalid rlp format: not list: `{0:?}``
     InvalidRLPFormatNotList(Vec<u8>),
@@ -29,19 +98,41 @@ pub enum Error {
     InvalidBlockNumberLength(usize),
     /// invalid block timestamp length: `{0}`
     InvalidBlockTimestampLength(usize),
+    /// header timestamp overflow: `{0}`
+    HeaderTimestampOverflow(u128),
     /// invalid validator address length: `{0}`
     InvalidValidatorAddressLength(usize),
+    /// invalid trie node: `{0:?}`
+    InvalidTrieNode(Vec<u8>),
+    /// invalid trie node reference: `{0:?}`
+    InvalidTrieNodeReference(Vec<u8>),
+    /// trie node not found in the proof: hash={0:?}
+    TrieNodeNotFound(H256),
+
+    /// invalid duration: nanos must be less than 1e9: {0}
+    InvalidDurationNanos(u32),
 
     /// from uint to u64 error: `{0}`
     FromUint64Error(ruint::FromUintError<u64>),
     /// from uint to u128 error: `{0}`
     FromUint128Error(ruint::FromUintError<u128>),
 
+    /// client is frozen: frozen_height={0:?}
+    ClientFrozen(Height),
+
     /// unexpected client type: `{0}`
     UnexpectedClientType(String),
 
     /// Invalid signature length: `{0}`
     InvalidSignatureLength(usize),
+    /// invalid signature: r is zero or not less than the curve order: r={0:?}
+    InvalidSignatureR(Vec<u8>),
+    /// invalid signature: s is zero or not less than the curve order: s={0:?}
+    InvalidSignatureS(Vec<u8>),
+    /// invalid signature: s is greater than the half of the curve order: s={0:?}
+    InvalidSignatureHighS(Vec<u8>),
+    /// invalid signature: recovery id must be 0, 1, 27 or 28: v={0}
+    InvalidSignatureRecoveryId(u8),
 
     /// untrusted validators and committed seals length mismatch: untrusted_validators_len={untrusted_validators_len} committed_seals_len={committed_seals_len}
     UntrustedValidatorsAndCommittedSealsLengthMismatch {
@@ -49,9 +140,17 @@ pub enum Error {
         committed_seals_len: usize,
     },
     /// insufficient trusted validators seals: actual={actual} threshold={threshold}
-    InsufficientTrustedValidatorsSeals { actual: usize, threshold: usize },
+    InsufficientTrustedValidatorsSeals {
+        actual: usize,
+        threshold: usize,
+        report: CommitSealsReport,
+    },
     /// insufficient untrusted validators seals: actual={actual} threshold={threshold}
-    InsuffientUntrustedValidatorsSeals { actual: usize, threshold: usize },
+    InsuffientUntrustedValidatorsSeals {
+        actual: usize,
+        threshold: usize,
+        report: CommitSealsReport,
+    },
 
     /// account not found: state_root={0:?} address={1:?}
     AccountNotFound(H256, Address),
@@ -65,12 +164,47 @@ pub enum Error {
         current_timestamp: Time,
         trusting_period_end: Time,
     },
+    /// processed time of the consensus state is not set, so the delay period cannot be verified
+    ProcessedTimeNotFound,
+    /// delay period has not passed: current_timestamp={current_timestamp} valid_timestamp={valid_timestamp}
+    DelayPeriodNotPassed {
+        current_timestamp: Time,
+        valid_timestamp: Time,
+    },
     /// header is coming from future: current_timestamp={current_timestamp} clock_drift={clock_drift:?} header_timestamp={header_timestamp}
     HeaderFromFuture {
         current_timestamp: Time,
         clock_drift: Duration,
         header_timestamp: Time,
     },
+    /// header height must be greater than the trusted height: trusted_height={trusted_height:?} header_height={header_height:?}
+    HeaderHeightNotGreaterThanTrustedHeight {
+        trusted_height: Height,
+        header_height: Height,
+    },
+    /// header timestamp must not be less than the trusted timestamp: trusted_timestamp={trusted_timestamp} header_timestamp={header_timestamp}
+    HeaderTimestampLessThanTrustedTimestamp {
+        trusted_timestamp: Time,
+        header_timestamp: Time,
+    },
+    /// backfill header timestamp must not be greater than the trusted timestamp: trusted_timestamp={trusted_timestamp} header_timestamp={header_timestamp}
+    BackfillHeaderTimestampGreaterThanTrustedTimestamp {
+        trusted_timestamp: Time,
+        header_timestamp: Time,
+    },
+    /// backfill is not allowed: latest_height={latest_height:?} header_height={header_height:?}
+    BackfillNotAllowed {
+        latest_height: Height,
+        header_height: Height,
+    },
+    /// membership value mismatch: path={path} expected={expected:?} actual={actual:?}
+    MembershipValueMismatch {
+        path: String,
+        expected: Option<Vec<u8>>,
+        actual: Option<Vec<u8>>,
+    },
+    /// path is reserved for the proxy message of the batch membership verification: {0}
+    ReservedMembershipPath(String),
     /// invalid header extra size: `{0}`
     InvalidHeaderExtraSize(usize),
     /// invalid header extra: contains committed seals: header={0:?}
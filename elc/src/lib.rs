@@ -4,6 +4,8 @@
 
 use light_client::LightClientRegistry;
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub mod client;
 pub mod client_state;
@@ -12,6 +14,13 @@ pub mod consensus_state;
 pub mod errors;
 pub mod header;
 pub mod message;
+#[cfg(test)]
+mod model_tests;
+pub mod path;
+pub mod pruning;
+pub mod seal;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 pub mod types;
 
 mod internal_prelude {
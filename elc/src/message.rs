@@ -1,17 +1,24 @@
 use crate::errors::Error;
 use crate::internal_prelude::*;
+use crate::types::{Address, H256};
 use besu_qbft_proto::ibc::{
-    core::client::v1::Height as RawHeight, lightclients::qbft::v1::Header as RawHeader,
+    core::client::v1::Height as RawHeight,
+    lightclients::qbft::v1::{
+        Checkpoint as RawCheckpoint, Header as RawHeader, RevisionUpgrade as RawRevisionUpgrade,
+    },
 };
 use light_client::types::proto::protobuf::Protobuf;
 use light_client::types::{Any, Height};
 use prost::Message;
 
 pub const BESU_QBFT_HEADER_TYPE_URL: &str = "/ibc.lightclients.qbft.v1.Header";
+pub const BESU_QBFT_REVISION_UPGRADE_TYPE_URL: &str = "/ibc.lightclients.qbft.v1.RevisionUpgrade";
+pub const BESU_QBFT_CHECKPOINT_TYPE_URL: &str = "/ibc.lightclients.qbft.v1.Checkpoint";
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ClientMessage {
     Header(Header),
+    RevisionUpgrade(RevisionUpgrade),
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -20,6 +27,7 @@ pub struct Header {
     pub seals: Vec<Vec<u8>>,
     pub trusted_height: Height,
     pub account_state_proof: Vec<u8>,
+    pub trusted_validators: Vec<Address>,
 }
 
 impl From<Header> for RawHeader {
@@ -32,6 +40,11 @@ impl From<Header> for RawHeader {
                 revision_height: value.trusted_height.revision_height(),
             }),
             account_state_proof: value.account_state_proof,
+            trusted_validators: value
+                .trusted_validators
+                .iter()
+                .map(|v| v.to_vec())
+                .collect(),
         }
     }
 }
@@ -59,6 +72,14 @@ impl TryFrom<RawHeader> for Header {
                 trusted_height.revision_height,
             ),
             account_state_proof: value.account_state_proof,
+            trusted_validators: value
+                .trusted_validators
+                .into_iter()
+                .map(|v| {
+                    Address::try_from(v.as_slice())
+                        .map_err(|_| Error::InvalidValidatorAddressLength(v.len()))
+                })
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -80,10 +101,100 @@ impl TryFrom<Any> for Header {
     }
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RevisionUpgrade {
+    pub header: Header,
+}
+
+impl From<RevisionUpgrade> for RawRevisionUpgrade {
+    fn from(value: RevisionUpgrade) -> Self {
+        RawRevisionUpgrade {
+            header: Some(value.header.into()),
+        }
+    }
+}
+
+impl From<RevisionUpgrade> for Any {
+    fn from(value: RevisionUpgrade) -> Self {
+        let raw_revision_upgrade = RawRevisionUpgrade::from(value);
+        let value = raw_revision_upgrade.encode_to_vec();
+        Any::new(BESU_QBFT_REVISION_UPGRADE_TYPE_URL.to_string(), value)
+    }
+}
+
+impl TryFrom<RawRevisionUpgrade> for RevisionUpgrade {
+    type Error = Error;
+
+    fn try_from(value: RawRevisionUpgrade) -> Result<Self, Self::Error> {
+        Ok(RevisionUpgrade {
+            header: value
+                .header
+                .ok_or(Error::InvalidRevisionUpgradeEmptyHeader)?
+                .try_into()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Checkpoint {
+    pub block_hash: H256,
+    pub besu_header_rlp: Vec<u8>,
+    pub account_state_proof: Vec<u8>,
+}
+
+impl From<Checkpoint> for RawCheckpoint {
+    fn from(value: Checkpoint) -> Self {
+        RawCheckpoint {
+            block_hash: value.block_hash.to_be_bytes_vec(),
+            besu_header_rlp: value.besu_header_rlp,
+            account_state_proof: value.account_state_proof,
+        }
+    }
+}
+
+impl From<Checkpoint> for Any {
+    fn from(value: Checkpoint) -> Self {
+        let raw_checkpoint = RawCheckpoint::from(value);
+        let value = raw_checkpoint.encode_to_vec();
+        Any::new(BESU_QBFT_CHECKPOINT_TYPE_URL.to_string(), value)
+    }
+}
+
+impl TryFrom<RawCheckpoint> for Checkpoint {
+    type Error = Error;
+
+    fn try_from(value: RawCheckpoint) -> Result<Self, Self::Error> {
+        Ok(Checkpoint {
+            block_hash: H256::try_from_be_slice(&value.block_hash)
+                .ok_or_else(|| Error::InvalidCheckpointBlockHashSize(value.block_hash.len()))?,
+            besu_header_rlp: value.besu_header_rlp,
+            account_state_proof: value.account_state_proof,
+        })
+    }
+}
+
+impl TryFrom<Any> for Checkpoint {
+    type Error = Error;
+
+    fn try_from(value: Any) -> Result<Self, Self::Error> {
+        let type_url = value.type_url.as_str();
+        let value = value.value.as_ref();
+
+        match type_url {
+            BESU_QBFT_CHECKPOINT_TYPE_URL => {
+                let raw_checkpoint = RawCheckpoint::decode(value).map_err(Error::Decode)?;
+                Checkpoint::try_from(raw_checkpoint)
+            }
+            _ => Err(Error::UnexpectedClientType(type_url.to_string())),
+        }
+    }
+}
+
 impl From<ClientMessage> for Any {
     fn from(value: ClientMessage) -> Self {
         match value {
             ClientMessage::Header(header) => header.into(),
+            ClientMessage::RevisionUpgrade(revision_upgrade) => revision_upgrade.into(),
         }
     }
 }
@@ -101,6 +212,12 @@ impl TryFrom<Any> for ClientMessage {
                 let header = Header::try_from(raw_header)?;
                 Ok(ClientMessage::Header(header))
             }
+            BESU_QBFT_REVISION_UPGRADE_TYPE_URL => {
+                let raw_revision_upgrade =
+                    RawRevisionUpgrade::decode(value).map_err(Error::Decode)?;
+                let revision_upgrade = RevisionUpgrade::try_from(raw_revision_upgrade)?;
+                Ok(ClientMessage::RevisionUpgrade(revision_upgrade))
+            }
             _ => Err(Error::UnexpectedClientType(type_url.to_string())),
         }
     }
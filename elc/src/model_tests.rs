@@ -0,0 +1,280 @@
+//! Property-based tests that drive the light client with random operations and compare it with a reference model
+extern crate std;
+
+use crate::client::BesuQBFTLightClient;
+use crate::client_state::ClientState;
+use crate::consensus_state::{ConsensusState, ValidatorSet};
+use crate::internal_prelude::*;
+use crate::test_utils::qbft::{generate_validators, QbftChainSimulator, SimulatedBlock};
+use crate::test_utils::trie::MockStateTrie;
+use crate::test_utils::MockContext;
+use crate::types::{gen_state_id, Address, U256};
+use alloc::collections::BTreeSet;
+use alloc::format;
+use core::time::Duration;
+use hex_literal::hex;
+use light_client::commitments::{CommitmentPrefix, StateID};
+use light_client::types::{ClientId, Height, Time};
+use light_client::{LightClient, UpdateClientResult};
+use proptest::prelude::*;
+use std::sync::OnceLock;
+
+const IBC_STORE_ADDRESS: Address = hex!("702e40245797c5a2108a566b3ce2bf14bc6af841");
+/// number of blocks after the initial block
+const BLOCKS: usize = 16;
+const INITIAL_HEIGHT: u64 = 100;
+const BASE_TIMESTAMP: u64 = 1_700_000_000;
+// the timestamps of the blocks are `BASE_TIMESTAMP + 10 * i + 5`, and the host timestamp is always `BASE_TIMESTAMP + 10 * n`,
+// so the model never has to decide the boundaries of the trusting period and the clock drift
+const BLOCK_PERIOD: u64 = 10;
+const TRUSTING_PERIOD: u64 = 100;
+const MAX_CLOCK_DRIFT: u64 = 10;
+
+struct FixtureBlock {
+    block: SimulatedBlock,
+    state: MockStateTrie,
+    /// state IDs of the consensus state indexed by `allow_backfill` of the client state, which is part of the state ID
+    state_ids: [StateID; 2],
+}
+
+impl FixtureBlock {
+    fn state_id(&self, allow_backfill: bool) -> StateID {
+        self.state_ids[allow_backfill as usize]
+    }
+}
+
+/// the chain whose block `i` has the height `INITIAL_HEIGHT + i` and the commitments `commitments/0..=i`
+fn fixture() -> &'static Vec<FixtureBlock> {
+    static FIXTURE: OnceLock<Vec<FixtureBlock>> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let mut chain = QbftChainSimulator::new(
+            generate_validators(4),
+            INITIAL_HEIGHT - 1,
+            BASE_TIMESTAMP + 5 - BLOCK_PERIOD,
+        );
+        chain.block_period = BLOCK_PERIOD;
+        let mut state = MockStateTrie::new();
+        (0..=BLOCKS)
+            .map(|i| {
+                state.set_ibc_commitment(IBC_STORE_ADDRESS, &path(i), &value(i));
+                let block = chain.next_block(state.state_root());
+                let consensus_state = ConsensusState {
+                    timestamp: time(block.timestamp),
+                    root: state.storage_root(&IBC_STORE_ADDRESS),
+                    validators: ValidatorSet::Addresses(block.validators.clone()),
+                    processed_time: None,
+                };
+                FixtureBlock {
+                    state_ids: [false, true].map(|allow_backfill| {
+                        gen_state_id(client_state(allow_backfill), consensus_state.clone()).unwrap()
+                    }),
+                    block,
+                    state: state.clone(),
+                }
+            })
+            .collect()
+    })
+}
+
+fn time(secs: u64) -> Time {
+    Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap()
+}
+
+fn height(i: usize) -> Height {
+    Height::new(0, INITIAL_HEIGHT + i as u64)
+}
+
+fn path(i: usize) -> String {
+    format!("commitments/{}", i)
+}
+
+fn value(i: usize) -> Vec<u8> {
+    vec![i as u8]
+}
+
+fn client_id() -> ClientId {
+    "hb-qbft-0".parse().unwrap()
+}
+
+fn client_state(allow_backfill: bool) -> ClientState {
+    ClientState {
+        chain_id: U256::from(2018u64),
+        ibc_store_address: IBC_STORE_ADDRESS,
+        latest_height: height(0),
+        trusting_period: Duration::from_secs(TRUSTING_PERIOD),
+        max_clock_drift: Duration::from_secs(MAX_CLOCK_DRIFT),
+        allow_backfill,
+        ..Default::default()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Update { target: usize, trusted: usize },
+    AdvanceClock(u64),
+    VerifyMembership(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (0..=BLOCKS, 0..=BLOCKS).prop_map(|(target, trusted)| Op::Update { target, trusted }),
+        1 => (0..5u64).prop_map(Op::AdvanceClock),
+        2 => (0..=BLOCKS).prop_map(Op::VerifyMembership),
+    ]
+}
+
+/// reference model of the client, where the blocks are identified by their indexes
+struct Model {
+    allow_backfill: bool,
+    /// seconds since `BASE_TIMESTAMP`
+    host: u64,
+    trusted: BTreeSet<usize>,
+    latest: usize,
+}
+
+impl Model {
+    fn timestamp(i: usize) -> u64 {
+        BLOCK_PERIOD * i as u64 + 5
+    }
+
+    fn is_expired(&self, i: usize) -> bool {
+        Self::timestamp(i) + TRUSTING_PERIOD <= self.host
+    }
+
+    fn is_verifiable(&self, i: usize) -> bool {
+        self.trusted.contains(&i) && !self.is_expired(i)
+    }
+
+    /// applies the update and returns true if it is expected to succeed
+    fn update(&mut self, target: usize, trusted: usize) -> bool {
+        if !self.is_verifiable(trusted) || Self::timestamp(target) > self.host + MAX_CLOCK_DRIFT {
+            return false;
+        }
+        if self.trusted.contains(&target) {
+            // the same header is a no-op
+            return true;
+        }
+        let is_backfill = if target > trusted {
+            target <= self.latest
+        } else {
+            true
+        };
+        if is_backfill && !self.allow_backfill {
+            return false;
+        }
+        self.trusted.insert(target);
+        if !is_backfill {
+            self.latest = target;
+        }
+        true
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_state_machine(
+        allow_backfill in any::<bool>(),
+        ops in prop::collection::vec(op(), 1..32),
+    ) {
+        let blocks = fixture();
+        let lc = BesuQBFTLightClient;
+        let mut model = Model {
+            allow_backfill,
+            host: BLOCK_PERIOD,
+            trusted: [0].into_iter().collect(),
+            latest: 0,
+        };
+        let mut ctx = MockContext::new(time(BASE_TIMESTAMP + model.host));
+
+        let initial_consensus_state = ConsensusState {
+            timestamp: time(blocks[0].block.timestamp),
+            root: blocks[0].state.storage_root(&IBC_STORE_ADDRESS),
+            validators: ValidatorSet::Addresses(blocks[0].block.validators.clone()),
+            processed_time: None,
+        };
+        let res = lc
+            .create_client(
+                &ctx,
+                client_state(allow_backfill).into(),
+                initial_consensus_state.clone().try_into().unwrap(),
+            )
+            .unwrap();
+        ctx.apply_create_client(
+            &client_id(),
+            client_state(allow_backfill).into(),
+            initial_consensus_state.try_into().unwrap(),
+            res.height,
+        );
+
+        for op in ops {
+            match op.clone() {
+                Op::Update { target, trusted } => {
+                    let header = blocks[target].block.to_header(
+                        height(trusted),
+                        blocks[target].state.account_proof(&IBC_STORE_ADDRESS),
+                    );
+                    let res = lc.update_client(&ctx, client_id(), header.into());
+                    let expected = model.update(target, trusted);
+                    prop_assert_eq!(res.is_ok(), expected, "{:?} {:?}", op, res);
+                    if let Ok(res) = res {
+                        let data = match res {
+                            UpdateClientResult::UpdateState(ref data) => data,
+                            UpdateClientResult::Misbehaviour(_) => {
+                                panic!("unexpected misbehaviour: {:?}", op)
+                            }
+                        };
+                        prop_assert_eq!(data.height, height(target));
+                        prop_assert_eq!(data.message.prev_height, Some(height(trusted)));
+                        prop_assert_eq!(
+                            data.message.prev_state_id,
+                            Some(blocks[trusted].state_id(allow_backfill))
+                        );
+                        prop_assert_eq!(data.message.post_height, height(target));
+                        prop_assert_eq!(
+                            data.message.post_state_id,
+                            blocks[target].state_id(allow_backfill)
+                        );
+                        ctx.apply_update_client(&client_id(), res);
+                    }
+                }
+                Op::AdvanceClock(n) => {
+                    model.host += BLOCK_PERIOD * n;
+                    ctx.set_host_timestamp(time(BASE_TIMESTAMP + model.host));
+                }
+                Op::VerifyMembership(i) => {
+                    let proof = blocks[i]
+                        .state
+                        .ibc_commitment_proof(&IBC_STORE_ADDRESS, &path(i));
+                    let res = lc.verify_membership(
+                        &ctx,
+                        client_id(),
+                        CommitmentPrefix::default(),
+                        path(i),
+                        value(i),
+                        height(i),
+                        proof,
+                    );
+                    prop_assert_eq!(res.is_ok(), model.is_verifiable(i), "{:?} {:?}", op, res);
+                    if let Ok(res) = res {
+                        prop_assert_eq!(res.message.height, height(i));
+                        prop_assert_eq!(
+                            res.message.state_id,
+                            blocks[i].state_id(allow_backfill)
+                        );
+                    }
+                }
+            }
+
+            prop_assert_eq!(
+                lc.latest_height(&ctx, &client_id()).unwrap(),
+                height(model.latest)
+            );
+            prop_assert_eq!(
+                ctx.consensus_state_heights(&client_id()),
+                model.trusted.iter().map(|i| height(*i)).collect::<Vec<_>>()
+            );
+        }
+    }
+}
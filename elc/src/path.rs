@@ -0,0 +1,93 @@
+//! Paths of the IBC commitments defined in ICS-24, which are passed to `verify_membership` and `verify_non_membership`
+use crate::internal_prelude::*;
+use alloc::format;
+use light_client::types::Height;
+
+/// path of the proxy message of the batch membership verification, whose value is the commitment to the verified items
+///
+/// it is not a path of ICS-24, so it is rejected by `verify_membership` and `verify_non_membership`
+pub const MEMBERSHIPS_PATH: &str = "memberships";
+
+pub fn client_state_path(client_id: &str) -> String {
+    format!("clients/{}/clientState", client_id)
+}
+
+pub fn consensus_state_path(client_id: &str, height: Height) -> String {
+    format!(
+        "clients/{}/consensusStates/{}-{}",
+        client_id,
+        height.revision_number(),
+        height.revision_height()
+    )
+}
+
+pub fn connection_path(connection_id: &str) -> String {
+    format!("connections/{}", connection_id)
+}
+
+pub fn channel_path(port_id: &str, channel_id: &str) -> String {
+    format!("channelEnds/ports/{}/channels/{}", port_id, channel_id)
+}
+
+pub fn next_sequence_recv_path(port_id: &str, channel_id: &str) -> String {
+    format!("nextSequenceRecv/ports/{}/channels/{}", port_id, channel_id)
+}
+
+pub fn packet_commitment_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "commitments/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
+
+pub fn packet_acknowledgement_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "acks/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
+
+pub fn packet_receipt_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "receipts/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(
+            client_state_path("07-tendermint-0"),
+            "clients/07-tendermint-0/clientState"
+        );
+        assert_eq!(
+            consensus_state_path("07-tendermint-0", Height::new(1, 100)),
+            "clients/07-tendermint-0/consensusStates/1-100"
+        );
+        assert_eq!(connection_path("connection-0"), "connections/connection-0");
+        assert_eq!(
+            channel_path("transfer", "channel-0"),
+            "channelEnds/ports/transfer/channels/channel-0"
+        );
+        assert_eq!(
+            next_sequence_recv_path("transfer", "channel-0"),
+            "nextSequenceRecv/ports/transfer/channels/channel-0"
+        );
+        assert_eq!(
+            packet_commitment_path("transfer", "channel-0", 1),
+            "commitments/ports/transfer/channels/channel-0/sequences/1"
+        );
+        assert_eq!(
+            packet_acknowledgement_path("transfer", "channel-0", 1),
+            "acks/ports/transfer/channels/channel-0/sequences/1"
+        );
+        assert_eq!(
+            packet_receipt_path("transfer", "channel-0", 1),
+            "receipts/ports/transfer/channels/channel-0/sequences/1"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Pruning of the expired consensus states, which the host must run
+//!
+//! `update_client` never removes consensus states because `HostClientReader` is read-only,
+//! so the consensus states are kept until the host calls `prune_consensus_states` with its store.
+use crate::client_state::ClientState;
+use crate::consensus_state::ConsensusState;
+use crate::errors::Error;
+use crate::internal_prelude::*;
+use light_client::types::{ClientId, Height, Time};
+use light_client::HostClientReader;
+
+/// store of the host that can iterate over and remove the consensus states of a client
+///
+/// NOTE: `HostClientReader` provides neither iteration over nor removal of the consensus states,
+/// so the host that owns the store must implement this trait to prune them.
+pub trait ConsensusStateStore: HostClientReader {
+    /// returns the heights of the consensus states of the client
+    fn consensus_state_heights(&self, client_id: &ClientId) -> Vec<Height>;
+
+    fn remove_consensus_state(&mut self, client_id: &ClientId, height: Height);
+}
+
+/// removes the consensus states of the client that can be pruned at the host timestamp, and returns their heights
+///
+/// this is not called by the light client, so the host must call it, e.g. after applying `update_client`
+pub fn prune_consensus_states<S: ConsensusStateStore + ?Sized>(
+    store: &mut S,
+    client_id: &ClientId,
+) -> Result<Vec<Height>, light_client::Error> {
+    let client_state: ClientState = store.client_state(client_id)?.try_into()?;
+    let consensus_states = store
+        .consensus_state_heights(client_id)
+        .into_iter()
+        .map(|height| {
+            let consensus_state = client_state
+                .consensus_state_from_any(store.consensus_state(client_id, &height)?, height)?;
+            Ok((height, consensus_state))
+        })
+        .collect::<Result<Vec<_>, light_client::Error>>()?;
+    let heights =
+        prunable_consensus_state_heights(&client_state, consensus_states, store.host_timestamp())?;
+    for height in heights.iter() {
+        store.remove_consensus_state(client_id, *height);
+    }
+    Ok(heights)
+}
+
+/// returns the heights of the consensus states that can be pruned at `host_timestamp`
+///
+/// a consensus state can be pruned if its trusting period has ended, but the one at the latest height of the client is always kept.
+pub fn prunable_consensus_state_heights<I>(
+    client_state: &ClientState,
+    consensus_states: I,
+    host_timestamp: Time,
+) -> Result<Vec<Height>, Error>
+where
+    I: IntoIterator<Item = (Height, ConsensusState)>,
+{
+    let mut heights = Vec::new();
+    for (height, consensus_state) in consensus_states {
+        if height == client_state.latest_height {
+            continue;
+        }
+        if client_state.is_expired(&consensus_state, host_timestamp)? {
+            heights.push(height);
+        }
+    }
+    Ok(heights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus_state::ValidatorSet;
+    use crate::test_utils::MockContext;
+    use crate::types::H256;
+    use core::time::Duration;
+
+    fn time(secs: u64) -> Time {
+        Time::from_unix_timestamp_nanos(secs as u128 * 1_000_000_000).unwrap()
+    }
+
+    fn consensus_state(secs: u64) -> ConsensusState {
+        ConsensusState {
+            timestamp: time(secs),
+            root: H256::from_be_bytes([1u8; 32]),
+            validators: ValidatorSet::Addresses(vec![[1u8; 20]]),
+            processed_time: None,
+        }
+    }
+
+    #[test]
+    fn test_prunable_consensus_state_heights() {
+        let mut client_state = ClientState {
+            latest_height: Height::new(0, 3),
+            trusting_period: Duration::from_secs(100),
+            ..Default::default()
+        };
+        let consensus_states = vec![
+            (Height::new(0, 1), consensus_state(1000)),
+            (Height::new(0, 2), consensus_state(1050)),
+            (Height::new(0, 3), consensus_state(1060)),
+        ];
+
+        assert_eq!(
+            prunable_consensus_state_heights(&client_state, consensus_states.clone(), time(1099))
+                .unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            prunable_consensus_state_heights(&client_state, consensus_states.clone(), time(1100))
+                .unwrap(),
+            vec![Height::new(0, 1)]
+        );
+        // the latest consensus state is always kept
+        assert_eq!(
+            prunable_consensus_state_heights(&client_state, consensus_states.clone(), time(2000))
+                .unwrap(),
+            vec![Height::new(0, 1), Height::new(0, 2)]
+        );
+
+        // consensus states never expire if the trusting period is zero
+        client_state.trusting_period = Duration::ZERO;
+        assert_eq!(
+            prunable_consensus_state_heights(&client_state, consensus_states, time(2000)).unwrap(),
+            vec![]
+        );
+    }
+    #[test]
+    fn test_prune_consensus_states() {
+        let client_id: ClientId = "hb-qbft-0".parse().unwrap();
+        let client_state = ClientState {
+            latest_height: Height::new(0, 3),
+            trusting_period: Duration::from_secs(100),
+            ..Default::default()
+        };
+        let mut ctx = MockContext::new(time(1099));
+        ctx.apply_create_client(
+            &client_id,
+            client_state.into(),
+            consensus_state(1060).try_into().unwrap(),
+            Height::new(0, 3),
+        );
+        ctx.store_consensus_state(
+            &client_id,
+            Height::new(0, 1),
+            consensus_state(1000).try_into().unwrap(),
+        );
+        ctx.store_consensus_state(
+            &client_id,
+            Height::new(0, 2),
+            consensus_state(1050).try_into().unwrap(),
+        );
+
+        assert_eq!(
+            prune_consensus_states(&mut ctx, &client_id).unwrap(),
+            vec![]
+        );
+        ctx.set_host_timestamp(time(1100));
+        assert_eq!(
+            prune_consensus_states(&mut ctx, &client_id).unwrap(),
+            vec![Height::new(0, 1)]
+        );
+        assert_eq!(
+            ctx.consensus_state_heights(&client_id),
+            vec![Height::new(0, 2), Height::new(0, 3)]
+        );
+        // the latest consensus state is always kept
+        ctx.set_host_timestamp(time(2000));
+        assert_eq!(
+            prune_consensus_states(&mut ctx, &client_id).unwrap(),
+            vec![Height::new(0, 2)]
+        );
+        assert_eq!(
+            ctx.consensus_state_heights(&client_id),
+            vec![Height::new(0, 3)]
+        );
+    }
+}
@@ -0,0 +1,562 @@
+//! Verification of the committed seals of QBFT headers
+use crate::commitment::{parse_signature, recover_address};
+use crate::consensus_state::ConsensusState;
+use crate::errors::Error;
+use crate::header::EthHeader;
+use crate::internal_prelude::*;
+use crate::message::Header;
+use crate::types::{Address, H256};
+use libsecp256k1::{RecoveryId, Signature};
+
+/// strategy to recover the signers of the committed seals
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SealRecoveryMode {
+    /// stops recovering the seals once both the trusting and untrusting thresholds are satisfied
+    #[default]
+    EarlyExit,
+    /// recovers all the seals to report the full signer set
+    Diagnostic,
+}
+
+/// status of a committed seal in `CommitSealsReport`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SealStatus {
+    /// the seal is empty
+    Missing,
+    /// the seal was not recovered because both thresholds had been satisfied
+    NotRecovered,
+    /// the signer of the well-formed seal cannot be recovered, which is only reported for the seals after both thresholds had been satisfied
+    Invalid(String),
+    /// the signer is the untrusted validator at the position of the seal
+    Signed,
+    /// the signer has already signed one of the preceding seals
+    Duplicate,
+    /// the signer is an untrusted validator at another position
+    Misplaced,
+    /// the signer is not an untrusted validator
+    UnknownSigner,
+}
+
+/// result of the recovery of a committed seal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealReport {
+    /// position of the seal, which corresponds to the position of the untrusted validator
+    pub position: usize,
+    pub signer: Option<Address>,
+    /// position of the signer in the trusted validators
+    pub trusted_position: Option<usize>,
+    pub status: SealStatus,
+}
+
+/// report of the committed seals verification, which tells which validators signed the header
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommitSealsReport {
+    pub trusted_validators: Vec<Address>,
+    pub untrusted_validators: Vec<Address>,
+    pub seals: Vec<SealReport>,
+    /// number of the distinct trusted validators that signed the header
+    pub trusted_signed: usize,
+    /// number of the untrusted validators that signed the seal at their positions
+    pub untrusted_signed: usize,
+}
+
+impl CommitSealsReport {
+    pub fn is_trusting_threshold_satisfied(&self) -> bool {
+        self.trusted_signed * 3 > self.trusted_validators.len()
+    }
+
+    pub fn is_untrusting_threshold_satisfied(&self) -> bool {
+        self.untrusted_signed * 3 >= self.untrusted_validators.len() * 2
+    }
+
+    /// returns the trusted validators whose seals were not found
+    pub fn missing_trusted_validators(&self) -> Vec<Address> {
+        self.trusted_validators
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.seals.iter().any(|s| s.trusted_position == Some(*i)))
+            .map(|(_, v)| *v)
+            .collect()
+    }
+
+    /// returns the untrusted validators whose seals were not found at their positions
+    pub fn missing_untrusted_validators(&self) -> Vec<Address> {
+        self.untrusted_validators
+            .iter()
+            .zip(self.seals.iter())
+            .filter(|(_, s)| s.status != SealStatus::Signed)
+            .map(|(v, _)| *v)
+            .collect()
+    }
+
+    /// returns the signers that are neither trusted nor untrusted validators
+    pub fn unknown_signers(&self) -> Vec<Address> {
+        self.seals
+            .iter()
+            .filter(|s| s.status == SealStatus::UnknownSigner && s.trusted_position.is_none())
+            .filter_map(|s| s.signer)
+            .collect()
+    }
+}
+
+/// verifies that the committed seals satisfy both thresholds:
+/// - trusting: more than 1/3 of `trusted_validators` signed the header
+/// - untrusting: at least 2/3 of `untrusted_validators` signed the header
+///
+/// a malformed seal, which is rejected by `parse_signature`, is always an error.
+/// the seals are checked in the order of the positions, and a well-formed seal whose signer cannot be recovered
+/// is an error until both thresholds are satisfied.
+/// the seals after that point never change the result, so the result does not depend on `mode` or the `rayon` feature,
+/// which only decide whether those seals are recovered for the report.
+/// if the `rayon` feature is enabled, all the seals are recovered in parallel regardless of `mode`.
+///
+/// CONTRACT: the order of `committed_seals` must be corresponding to the order of `untrusted_validators`
+pub fn verify_commit_seals(
+    trusted_validators: &[Address],
+    untrusted_validators: &[Address],
+    committed_seals: &[Vec<u8>],
+    commit_hash: H256,
+    mode: SealRecoveryMode,
+) -> Result<CommitSealsReport, Error> {
+    if untrusted_validators.len() != committed_seals.len() {
+        return Err(Error::UntrustedValidatorsAndCommittedSealsLengthMismatch {
+            untrusted_validators_len: untrusted_validators.len(),
+            committed_seals_len: committed_seals.len(),
+        });
+    }
+    let signatures = committed_seals
+        .iter()
+        .map(|seal| {
+            (!seal.is_empty())
+                .then(|| parse_signature(seal))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut recovered = recover_signers_in_parallel(commit_hash, &signatures).map(Vec::into_iter);
+    let mut tally = SealTally::new(trusted_validators, untrusted_validators);
+    let mut seals = Vec::with_capacity(signatures.len());
+    for (position, signature) in signatures.iter().enumerate() {
+        let signer = match (recovered.as_mut().and_then(|it| it.next()), signature) {
+            (Some(signer), _) => signer,
+            (None, None) => None,
+            (None, Some(_)) if mode == SealRecoveryMode::EarlyExit && tally.is_satisfied() => {
+                seals.push(SealReport {
+                    position,
+                    signer: None,
+                    trusted_position: None,
+                    status: SealStatus::NotRecovered,
+                });
+                continue;
+            }
+            (None, Some(signature)) => Some(recover_signer(commit_hash, signature)),
+        };
+        let report = match signer {
+            Some(Ok(signer)) => tally.add(position, signer),
+            Some(Err(e)) if !tally.is_satisfied() => return Err(e),
+            Some(Err(e)) => SealReport {
+                position,
+                signer: None,
+                trusted_position: None,
+                status: SealStatus::Invalid(e.to_string()),
+            },
+            None => SealReport {
+                position,
+                signer: None,
+                trusted_position: None,
+                status: SealStatus::Missing,
+            },
+        };
+        seals.push(report);
+    }
+    tally.finish(seals)
+}
+
+/// recovers the committed seals with the diagnostic mode, and returns the report even if the thresholds are not satisfied
+///
+/// this is intended for debugging which validators did not sign `header`, and it does not verify the other fields of the header
+pub fn diagnose_commit_seals(
+    trusted_consensus_state: &ConsensusState,
+    header: &Header,
+) -> Result<CommitSealsReport, Error> {
+    let eth_header = EthHeader::parse(&header.besu_header_rlp)?;
+    let trusted_validators = trusted_consensus_state
+        .validators
+        .resolve(&header.trusted_validators)?;
+    match verify_commit_seals(
+        &trusted_validators,
+        &eth_header.extra.validators,
+        &header.seals,
+        eth_header.commit_hash()?,
+        SealRecoveryMode::Diagnostic,
+    ) {
+        Ok(report)
+        | Err(Error::InsufficientTrustedValidatorsSeals { report, .. })
+        | Err(Error::InsuffientUntrustedValidatorsSeals { report, .. }) => Ok(report),
+        Err(e) => Err(e),
+    }
+}
+
+fn recover_signer(
+    commit_hash: H256,
+    signature: &(Signature, RecoveryId),
+) -> Result<Address, Error> {
+    recover_address(commit_hash, &signature.0, &signature.1)
+}
+
+#[cfg(not(feature = "rayon"))]
+fn recover_signers_in_parallel(
+    _commit_hash: H256,
+    _signatures: &[Option<(Signature, RecoveryId)>],
+) -> Option<Vec<Option<Result<Address, Error>>>> {
+    None
+}
+
+#[cfg(feature = "rayon")]
+fn recover_signers_in_parallel(
+    commit_hash: H256,
+    signatures: &[Option<(Signature, RecoveryId)>],
+) -> Option<Vec<Option<Result<Address, Error>>>> {
+    use rayon::prelude::*;
+    Some(
+        signatures
+            .par_iter()
+            .map(|signature| {
+                signature
+                    .as_ref()
+                    .map(|signature| recover_signer(commit_hash, signature))
+            })
+            .collect(),
+    )
+}
+
+/// counts the seals of the trusted and untrusted validators
+struct SealTally<'a> {
+    trusted_validators: &'a [Address],
+    untrusted_validators: &'a [Address],
+    /// whether the trusted validator at each position has been counted
+    trusted_marked: Vec<bool>,
+    trusted_signed: usize,
+    untrusted_signed: usize,
+    signers: Vec<Address>,
+}
+
+impl<'a> SealTally<'a> {
+    fn new(trusted_validators: &'a [Address], untrusted_validators: &'a [Address]) -> Self {
+        Self {
+            trusted_validators,
+            untrusted_validators,
+            trusted_marked: vec![false; trusted_validators.len()],
+            trusted_signed: 0,
+            untrusted_signed: 0,
+            signers: Vec::new(),
+        }
+    }
+
+    /// counts `signer` of the seal at `position`
+    ///
+    /// a trusted validator is counted at most once even if it signed several seals,
+    /// and an untrusted validator is counted only if the seal is at its position
+    fn add(&mut self, position: usize, signer: Address) -> SealReport {
+        let trusted_position = self.trusted_validators.iter().position(|v| *v == signer);
+        if let Some(i) = trusted_position {
+            if !self.trusted_marked[i] {
+                self.trusted_marked[i] = true;
+                self.trusted_signed += 1;
+            }
+        }
+        let status = if self.untrusted_validators[position] == signer {
+            self.untrusted_signed += 1;
+            SealStatus::Signed
+        } else if self.signers.contains(&signer) {
+            SealStatus::Duplicate
+        } else if self.untrusted_validators.contains(&signer) {
+            SealStatus::Misplaced
+        } else {
+            SealStatus::UnknownSigner
+        };
+        self.signers.push(signer);
+        SealReport {
+            position,
+            signer: Some(signer),
+            trusted_position,
+            status,
+        }
+    }
+
+    fn is_satisfied(&self) -> bool {
+        self.trusted_signed * 3 > self.trusted_validators.len()
+            && self.untrusted_signed * 3 >= self.untrusted_validators.len() * 2
+    }
+
+    fn finish(self, seals: Vec<SealReport>) -> Result<CommitSealsReport, Error> {
+        let report = CommitSealsReport {
+            trusted_validators: self.trusted_validators.to_vec(),
+            untrusted_validators: self.untrusted_validators.to_vec(),
+            seals,
+            trusted_signed: self.trusted_signed,
+            untrusted_signed: self.untrusted_signed,
+        };
+        if !report.is_trusting_threshold_satisfied() {
+            Err(Error::InsufficientTrustedValidatorsSeals {
+                actual: report.trusted_signed * 3,
+                threshold: report.trusted_validators.len(),
+                report,
+            })
+        } else if !report.is_untrusting_threshold_satisfied() {
+            Err(Error::InsuffientUntrustedValidatorsSeals {
+                actual: report.untrusted_signed * 3,
+                threshold: report.untrusted_validators.len() * 2,
+                report,
+            })
+        } else {
+            Ok(report)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus_state::ValidatorSet;
+    use crate::test_utils::qbft::{
+        generate_validators, QbftChainSimulator, QbftValidator, SimulatedBlock,
+    };
+    use crate::types::U256;
+    use light_client::types::{Height, Time};
+
+    #[test]
+    fn test_verify_commit_seals() {
+        let validators = generate_validators(4);
+        let addresses: Vec<Address> = validators.iter().map(|v| v.address).collect();
+        let mut chain = QbftChainSimulator::new(validators.clone(), 100, 1000);
+        let block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+        let verify = |seals: &[Vec<u8>]| {
+            verify_commit_seals(
+                &addresses,
+                &addresses,
+                seals,
+                block.commit_hash,
+                SealRecoveryMode::EarlyExit,
+            )
+        };
+
+        // all validators signed
+        assert!(verify(&block.seals).is_ok());
+
+        // 3 of 4 validators signed
+        let mut b = block.clone();
+        b.remove_seal(0);
+        assert!(verify(&b.seals).is_ok());
+
+        // 2 of 4 validators signed, which satisfies only the trusting threshold
+        b.remove_seal(1);
+        assert!(matches!(
+            verify(&b.seals),
+            Err(Error::InsuffientUntrustedValidatorsSeals { .. })
+        ));
+
+        // 1 of 4 validators signed
+        b.remove_seal(2);
+        assert!(matches!(
+            verify(&b.seals),
+            Err(Error::InsufficientTrustedValidatorsSeals { .. })
+        ));
+
+        // the seals signed over a different hash are not counted
+        let mut b = block.clone();
+        b.corrupt_seal(0, &validators[0]);
+        b.corrupt_seal(1, &validators[1]);
+        assert!(matches!(
+            verify(&b.seals),
+            Err(Error::InsuffientUntrustedValidatorsSeals { .. })
+        ));
+
+        // the seals of non-validators are not counted
+        let byzantine = QbftValidator::from_seed(100);
+        let mut b = block.clone();
+        b.set_seal(0, &byzantine);
+        b.set_seal(1, &byzantine);
+        assert!(matches!(
+            verify(&b.seals),
+            Err(Error::InsuffientUntrustedValidatorsSeals { .. })
+        ));
+
+        // the duplicated seals are counted only once
+        let seals = vec![block.seals[0].clone(); 4];
+        assert!(matches!(
+            verify(&seals),
+            Err(Error::InsufficientTrustedValidatorsSeals { .. })
+        ));
+
+        // the seals are not corresponding to the validators
+        assert!(matches!(
+            verify(&block.seals[..3]),
+            Err(Error::UntrustedValidatorsAndCommittedSealsLengthMismatch { .. })
+        ));
+
+        // the seal has an invalid length
+        let mut b = block.clone();
+        b.seals[0] = vec![0u8; 64];
+        assert!(verify(&b.seals).is_err());
+    }
+
+    #[test]
+    fn test_verify_commit_seals_validator_rotation() {
+        let validators = generate_validators(8);
+        let trusted: Vec<Address> = validators[..4].iter().map(|v| v.address).collect();
+
+        // 2 of 4 trusted validators remain in the new validator set
+        let mut chain = QbftChainSimulator::new(validators[..4].to_vec(), 100, 1000);
+        chain.set_validators(validators[2..6].to_vec());
+        let block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+        assert!(verify_commit_seals(
+            &trusted,
+            &block.validators,
+            &block.seals,
+            block.commit_hash,
+            SealRecoveryMode::EarlyExit,
+        )
+        .is_ok());
+
+        // only 1 of 4 trusted validators remains in the new validator set
+        chain.set_validators(validators[3..7].to_vec());
+        let block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+        assert!(matches!(
+            verify_commit_seals(
+                &trusted,
+                &block.validators,
+                &block.seals,
+                block.commit_hash,
+                SealRecoveryMode::EarlyExit,
+            ),
+            Err(Error::InsufficientTrustedValidatorsSeals { .. })
+        ));
+    }
+
+    #[test]
+    fn test_seal_recovery_mode() {
+        let validators = generate_validators(4);
+        let addresses: Vec<Address> = validators.iter().map(|v| v.address).collect();
+        let mut chain = QbftChainSimulator::new(validators, 100, 1000);
+        let block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+        let verify = |seals: &[Vec<u8>], mode| {
+            verify_commit_seals(&addresses, &addresses, seals, block.commit_hash, mode)
+        };
+
+        let report = verify(&block.seals, SealRecoveryMode::Diagnostic).unwrap();
+        assert_eq!(report.trusted_signed, 4);
+        assert_eq!(report.untrusted_signed, 4);
+        for (i, seal) in report.seals.iter().enumerate() {
+            assert_eq!(seal.position, i);
+            assert_eq!(seal.signer, Some(addresses[i]));
+            assert_eq!(seal.trusted_position, Some(i));
+            assert_eq!(seal.status, SealStatus::Signed);
+        }
+
+        // both thresholds are satisfied by the first 3 seals, but the malformed last one is still an error
+        let mut seals = block.seals.clone();
+        seals[3] = vec![0u8; 64];
+        for mode in [SealRecoveryMode::EarlyExit, SealRecoveryMode::Diagnostic] {
+            assert!(matches!(
+                verify(&seals, mode),
+                Err(Error::InvalidSignatureLength(64))
+            ));
+        }
+        seals.swap(0, 3);
+        for mode in [SealRecoveryMode::EarlyExit, SealRecoveryMode::Diagnostic] {
+            assert!(matches!(
+                verify(&seals, mode),
+                Err(Error::InvalidSignatureLength(64))
+            ));
+        }
+
+        // the well-formed seal whose signer cannot be recovered after the thresholds are satisfied is only reported
+        let unrecoverable = |seal: &[u8]| {
+            let mut seal = seal.to_vec();
+            // 5 is not the x-coordinate of a point on the curve
+            seal[..32].copy_from_slice(&U256::from(5u64).to_be_bytes::<32>());
+            seal
+        };
+        let mut seals = block.seals.clone();
+        seals[3] = unrecoverable(&seals[3]);
+        let report = verify(&seals, SealRecoveryMode::Diagnostic).unwrap();
+        assert!(matches!(report.seals[3].status, SealStatus::Invalid(_)));
+        assert_eq!(report.untrusted_signed, 3);
+        let report = verify(&seals, SealRecoveryMode::EarlyExit).unwrap();
+        #[cfg(not(feature = "rayon"))]
+        assert_eq!(report.seals[3].status, SealStatus::NotRecovered);
+        #[cfg(feature = "rayon")]
+        assert!(matches!(report.seals[3].status, SealStatus::Invalid(_)));
+
+        // the same seal before the thresholds are satisfied is an error in both modes
+        let mut seals = block.seals.clone();
+        seals[0] = unrecoverable(&seals[0]);
+        for mode in [SealRecoveryMode::EarlyExit, SealRecoveryMode::Diagnostic] {
+            assert!(matches!(verify(&seals, mode), Err(Error::Secp256k1(_))));
+        }
+    }
+
+    #[test]
+    fn test_commit_seals_report() {
+        let validators = generate_validators(5);
+        let addresses: Vec<Address> = validators.iter().map(|v| v.address).collect();
+        let trusted_validators = vec![addresses[2], addresses[4]];
+        let byzantine = QbftValidator::from_seed(100);
+        let mut chain = QbftChainSimulator::new(validators[..4].to_vec(), 100, 1000);
+        let mut block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+        block.remove_seal(0);
+        block.set_seal(1, &validators[2]);
+        block.set_seal(3, &byzantine);
+
+        let err = verify_commit_seals(
+            &trusted_validators,
+            &block.validators,
+            &block.seals,
+            block.commit_hash,
+            SealRecoveryMode::Diagnostic,
+        )
+        .unwrap_err();
+        let report = match err {
+            Error::InsuffientUntrustedValidatorsSeals { report, .. } => report,
+            e => panic!("unexpected error: {:?}", e),
+        };
+        let statuses: Vec<SealStatus> = report.seals.iter().map(|s| s.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                SealStatus::Missing,
+                SealStatus::Misplaced,
+                SealStatus::Signed,
+                SealStatus::UnknownSigner,
+            ]
+        );
+        assert_eq!(report.trusted_signed, 1);
+        assert_eq!(report.untrusted_signed, 1);
+        assert_eq!(
+            report.missing_untrusted_validators(),
+            vec![addresses[0], addresses[1], addresses[3]]
+        );
+        assert_eq!(report.seals[1].trusted_position, Some(0));
+        assert_eq!(report.missing_trusted_validators(), vec![addresses[4]]);
+        assert_eq!(report.unknown_signers(), vec![byzantine.address]);
+
+        // the same signer at another position is a duplicate
+        block.set_seal(3, &validators[2]);
+        let report = diagnose(&block, &trusted_validators);
+        assert_eq!(report.seals[3].status, SealStatus::Duplicate);
+    }
+
+    fn diagnose(block: &SimulatedBlock, trusted_validators: &[Address]) -> CommitSealsReport {
+        let consensus_state = ConsensusState {
+            timestamp: Time::from_unix_timestamp_nanos(0).unwrap(),
+            root: H256::ZERO,
+            validators: ValidatorSet::Addresses(trusted_validators.to_vec()),
+            processed_time: None,
+        };
+        diagnose_commit_seals(
+            &consensus_state,
+            &block.to_header(Height::new(0, 100), vec![]),
+        )
+        .unwrap()
+    }
+}
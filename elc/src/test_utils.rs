@@ -0,0 +1,168 @@
+//! In-memory host context for testing the light client without LCP
+use crate::internal_prelude::*;
+use crate::pruning::ConsensusStateStore;
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+use light_client::types::{Any, ClientId, Height, Time};
+use light_client::{ClientReader, HostClientReader, HostContext, UpdateClientResult};
+use store::KVStore;
+
+pub mod qbft;
+pub mod trie;
+
+/// in-memory implementation of `HostClientReader` whose host timestamp can be controlled by the test
+#[derive(Clone, Debug, Default)]
+pub struct MockContext {
+    pub host_timestamp: Option<Time>,
+    client_states: BTreeMap<String, Any>,
+    consensus_states: BTreeMap<(String, u64, u64), Any>,
+    kv: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MockContext {
+    pub fn new(host_timestamp: Time) -> Self {
+        Self {
+            host_timestamp: Some(host_timestamp),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_host_timestamp(&mut self, host_timestamp: Time) {
+        self.host_timestamp = Some(host_timestamp);
+    }
+
+    pub fn advance_host_timestamp(&mut self, duration: Duration) {
+        let host_timestamp = self.host_timestamp();
+        self.host_timestamp = Some(
+            Time::from_unix_timestamp_nanos(
+                host_timestamp.as_unix_timestamp_nanos() + duration.as_nanos(),
+            )
+            .unwrap(),
+        );
+    }
+
+    pub fn store_client_state(&mut self, client_id: &ClientId, client_state: Any) {
+        self.client_states
+            .insert(client_id.to_string(), client_state);
+    }
+
+    pub fn store_consensus_state(
+        &mut self,
+        client_id: &ClientId,
+        height: Height,
+        consensus_state: Any,
+    ) {
+        self.consensus_states.insert(
+            (
+                client_id.to_string(),
+                height.revision_number(),
+                height.revision_height(),
+            ),
+            consensus_state,
+        );
+    }
+
+    pub fn remove_consensus_state(&mut self, client_id: &ClientId, height: Height) {
+        self.consensus_states.remove(&(
+            client_id.to_string(),
+            height.revision_number(),
+            height.revision_height(),
+        ));
+    }
+
+    /// returns the heights of the consensus states of the client in ascending order
+    pub fn consensus_state_heights(&self, client_id: &ClientId) -> Vec<Height> {
+        let client_id = client_id.to_string();
+        self.consensus_states
+            .keys()
+            .filter(|(id, _, _)| *id == client_id)
+            .map(|(_, revision_number, revision_height)| {
+                Height::new(*revision_number, *revision_height)
+            })
+            .collect()
+    }
+
+    /// stores the states of `create_client` as the LCP host does
+    pub fn apply_create_client(
+        &mut self,
+        client_id: &ClientId,
+        any_client_state: Any,
+        any_consensus_state: Any,
+        height: Height,
+    ) {
+        self.store_client_state(client_id, any_client_state);
+        self.store_consensus_state(client_id, height, any_consensus_state);
+    }
+
+    /// stores the states of `update_client` as the LCP host does
+    pub fn apply_update_client(&mut self, client_id: &ClientId, result: UpdateClientResult) {
+        match result {
+            UpdateClientResult::UpdateState(data) => {
+                self.store_client_state(client_id, data.new_any_client_state);
+                self.store_consensus_state(client_id, data.height, data.new_any_consensus_state);
+            }
+            UpdateClientResult::Misbehaviour(data) => {
+                self.store_client_state(client_id, data.new_any_client_state);
+            }
+        }
+    }
+}
+
+impl KVStore for MockContext {
+    fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.kv.insert(key, value);
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.kv.get(key).cloned()
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.kv.remove(key);
+    }
+}
+
+impl HostContext for MockContext {
+    fn host_timestamp(&self) -> Time {
+        self.host_timestamp
+            .expect("host timestamp must be set before using the context")
+    }
+}
+
+impl ClientReader for MockContext {
+    fn client_state(&self, client_id: &ClientId) -> Result<Any, light_client::Error> {
+        self.client_states
+            .get(&client_id.to_string())
+            .cloned()
+            .ok_or_else(|| light_client::Error::client_state_not_found(client_id.clone()))
+    }
+
+    fn consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Any, light_client::Error> {
+        self.consensus_states
+            .get(&(
+                client_id.to_string(),
+                height.revision_number(),
+                height.revision_height(),
+            ))
+            .cloned()
+            .ok_or_else(|| {
+                light_client::Error::consensus_state_not_found(client_id.clone(), *height)
+            })
+    }
+}
+
+impl HostClientReader for MockContext {}
+
+impl ConsensusStateStore for MockContext {
+    fn consensus_state_heights(&self, client_id: &ClientId) -> Vec<Height> {
+        MockContext::consensus_state_heights(self, client_id)
+    }
+
+    fn remove_consensus_state(&mut self, client_id: &ClientId, height: Height) {
+        MockContext::remove_consensus_state(self, client_id, height)
+    }
+}
@@ -0,0 +1,258 @@
+//! Deterministic QBFT chain simulator, which builds Besu headers signed by generated validators
+use crate::commitment::{address_from_pubkey, keccak256};
+use crate::internal_prelude::*;
+use crate::message::Header;
+use crate::types::{Address, H256};
+use hex_literal::hex;
+use libsecp256k1::{Message, PublicKey, SecretKey};
+use light_client::types::Height;
+use rlp::RlpStream;
+
+/// keccak256 hash of the RLP encoded empty list
+const EMPTY_OMMERS_HASH: [u8; 32] =
+    hex!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347");
+/// mix hash of QBFT blocks: "ctical byzantine fault tolerance"
+const QBFT_MIX_HASH: [u8; 32] =
+    hex!("63746963616c2062797a616e74696e65206661756c7420746f6c6572616e6365");
+
+#[derive(Clone, Debug)]
+pub struct QbftValidator {
+    secret_key: SecretKey,
+    pub address: Address,
+}
+
+impl QbftValidator {
+    /// generates a validator whose key is derived from `seed` deterministically
+    pub fn from_seed(seed: u64) -> Self {
+        let mut i = 0u64;
+        loop {
+            let bz = keccak256(&[seed.to_be_bytes(), i.to_be_bytes()].concat());
+            if let Ok(secret_key) = SecretKey::parse(&bz) {
+                let address = address_from_pubkey(&PublicKey::from_secret_key(&secret_key));
+                return Self {
+                    secret_key,
+                    address,
+                };
+            }
+            i += 1;
+        }
+    }
+
+    /// returns the committed seal for `commit_hash`, which is `r || s || recovery_id`
+    pub fn sign(&self, commit_hash: H256) -> Vec<u8> {
+        let (signature, recovery_id) = libsecp256k1::sign(
+            &Message::parse(&commit_hash.to_be_bytes()),
+            &self.secret_key,
+        );
+        let mut seal = signature.serialize().to_vec();
+        seal.push(recovery_id.serialize());
+        seal
+    }
+}
+
+/// generates `n` validators from the seeds `0..n`
+pub fn generate_validators(n: usize) -> Vec<QbftValidator> {
+    (0..n as u64).map(QbftValidator::from_seed).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulatedBlock {
+    /// RLP encoded header, which does not include the committed seals in the extra data
+    pub header_rlp: Vec<u8>,
+    pub commit_hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub state_root: H256,
+    pub round: u32,
+    /// validators in the extra data of the header
+    pub validators: Vec<Address>,
+    /// committed seals in the order of `validators`, where an empty seal means the validator did not sign
+    pub seals: Vec<Vec<u8>>,
+}
+
+impl SimulatedBlock {
+    /// returns the header message to update the client with this block
+    pub fn to_header(&self, trusted_height: Height, account_state_proof: Vec<u8>) -> Header {
+        Header {
+            besu_header_rlp: self.header_rlp.clone(),
+            seals: self.seals.clone(),
+            trusted_height,
+            account_state_proof,
+            trusted_validators: vec![],
+        }
+    }
+
+    /// removes the seal of the validator at `index`
+    pub fn remove_seal(&mut self, index: usize) {
+        self.seals[index] = vec![];
+    }
+
+    /// replaces the seal of the validator at `index` with a signature over a different hash
+    pub fn corrupt_seal(&mut self, index: usize, validator: &QbftValidator) {
+        let commit_hash: [u8; 32] = self.commit_hash.to_be_bytes();
+        let hash = H256::from_be_bytes(keccak256(&commit_hash));
+        self.seals[index] = validator.sign(hash);
+    }
+
+    /// replaces the seal at `index` with a seal of `signer`, which may not be a validator of the block
+    pub fn set_seal(&mut self, index: usize, signer: &QbftValidator) {
+        self.seals[index] = signer.sign(self.commit_hash);
+    }
+
+    /// returns the RLP encoded header including the non-empty committed seals, as Besu stores it
+    pub fn sealed_header_rlp(&self) -> Vec<u8> {
+        let seals: Vec<Vec<u8>> = self
+            .seals
+            .iter()
+            .filter(|seal| !seal.is_empty())
+            .cloned()
+            .collect();
+        encode_header(
+            self.number,
+            self.timestamp,
+            self.state_root,
+            &encode_extra(&self.validators, self.round, &seals),
+        )
+    }
+}
+
+/// simulator of a QBFT network whose blocks are built deterministically
+#[derive(Clone, Debug)]
+pub struct QbftChainSimulator {
+    pub validators: Vec<QbftValidator>,
+    pub number: u64,
+    pub timestamp: u64,
+    pub round: u32,
+    /// seconds between consecutive blocks
+    pub block_period: u64,
+}
+
+impl QbftChainSimulator {
+    pub fn new(validators: Vec<QbftValidator>, number: u64, timestamp: u64) -> Self {
+        Self {
+            validators,
+            number,
+            timestamp,
+            round: 0,
+            block_period: 1,
+        }
+    }
+
+    /// replaces the validators of the next blocks
+    pub fn set_validators(&mut self, validators: Vec<QbftValidator>) {
+        self.validators = validators;
+    }
+
+    /// builds the next block signed by all validators
+    pub fn next_block(&mut self, state_root: H256) -> SimulatedBlock {
+        self.number += 1;
+        self.timestamp += self.block_period;
+        self.build_block(self.number, self.timestamp, state_root)
+    }
+
+    /// builds a block signed by all validators without advancing the chain
+    pub fn build_block(&self, number: u64, timestamp: u64, state_root: H256) -> SimulatedBlock {
+        let validators: Vec<Address> = self.validators.iter().map(|v| v.address).collect();
+        let header_rlp = encode_header(
+            number,
+            timestamp,
+            state_root,
+            &encode_extra(&validators, self.round, &[]),
+        );
+        let commit_hash = H256::from_be_bytes(keccak256(&header_rlp));
+        let seals = self
+            .validators
+            .iter()
+            .map(|v| v.sign(commit_hash))
+            .collect();
+        SimulatedBlock {
+            header_rlp,
+            commit_hash,
+            number,
+            timestamp,
+            state_root,
+            round: self.round,
+            validators,
+            seals,
+        }
+    }
+}
+
+/// encodes the QBFT extra data: `[vanity, validators, vote, round, committed_seals]`
+pub fn encode_extra(validators: &[Address], round: u32, committed_seals: &[Vec<u8>]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(5);
+    stream.append(&vec![0u8; 32]);
+    stream.begin_list(validators.len());
+    for validator in validators {
+        stream.append(&validator.to_vec());
+    }
+    // no vote
+    stream.begin_list(0);
+    stream.append(&round);
+    stream.begin_list(committed_seals.len());
+    for seal in committed_seals {
+        stream.append(seal);
+    }
+    stream.out().to_vec()
+}
+
+/// encodes the Besu header with the given fields, and the other fields are filled with the constants of QBFT
+pub fn encode_header(number: u64, timestamp: u64, state_root: H256, extra: &[u8]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(15);
+    // parent hash
+    stream.append(&keccak256(&number.to_be_bytes()).to_vec());
+    stream.append(&EMPTY_OMMERS_HASH.to_vec());
+    // coinbase
+    stream.append(&vec![0u8; 20]);
+    stream.append(&state_root.to_be_bytes::<32>().to_vec());
+    // transactions root and receipts root
+    stream.append(&vec![0u8; 32]);
+    stream.append(&vec![0u8; 32]);
+    // logs bloom
+    stream.append(&vec![0u8; 256]);
+    // difficulty
+    stream.append(&1u64);
+    stream.append(&number);
+    // gas limit and gas used
+    stream.append(&30_000_000u64);
+    stream.append(&0u64);
+    stream.append(&timestamp);
+    stream.append(&extra.to_vec());
+    stream.append(&QBFT_MIX_HASH.to_vec());
+    // nonce
+    stream.append(&vec![0u8; 8]);
+    stream.out().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::verify_signature;
+    use crate::header::EthHeader;
+
+    #[test]
+    fn test_simulated_block() {
+        let validators = generate_validators(4);
+        let mut chain = QbftChainSimulator::new(validators.clone(), 100, 1700000000);
+        chain.round = 2;
+        let block = chain.next_block(H256::from_be_bytes([1u8; 32]));
+
+        let header = EthHeader::parse(&block.header_rlp).unwrap();
+        assert_eq!(header.number, crate::types::U256::from(101u64));
+        assert_eq!(header.timestamp, crate::types::U256::from(1700000001u64));
+        assert_eq!(header.state_root, block.state_root);
+        assert_eq!(header.extra.round, 2);
+        assert_eq!(header.extra.validators, block.validators);
+        assert_eq!(header.commit_hash().unwrap(), block.commit_hash);
+        for (validator, seal) in validators.iter().zip(block.seals.iter()) {
+            assert_eq!(
+                verify_signature(block.commit_hash, seal).unwrap(),
+                validator.address
+            );
+        }
+
+        let sealed = EthHeader::parse(&block.sealed_header_rlp()).unwrap();
+        assert_eq!(sealed.extra.committed_seals, block.seals);
+        assert!(sealed.commit_hash().is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! In-memory Merkle Patricia Trie, which produces the state root, storage roots and EIP-1186 proofs for tests
+use crate::commitment::{
+    calculate_ibc_commitment_storage_key, calculate_ibc_commitment_storage_value,
+    encode_eip1184_rlp_multiproof, keccak256,
+};
+use crate::internal_prelude::*;
+use crate::types::{Address, H256, U256};
+use alloc::collections::BTreeMap;
+use hex_literal::hex;
+use rlp::RlpStream;
+
+/// keccak256 hash of the empty code
+pub const EMPTY_CODE_HASH: [u8; 32] =
+    hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+
+/// secure trie whose keys are hashed with keccak256 as the state and storage tries of Ethereum
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTrie {
+    /// nibbles of the hashed key => value
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+        self.entries.insert(to_nibbles(&keccak256(key)), value);
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.entries.remove(&to_nibbles(&keccak256(key)))
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+        self.entries.get(&to_nibbles(&keccak256(key)))
+    }
+
+    pub fn root(&self) -> H256 {
+        let entries = self.sorted_entries();
+        H256::from_be_bytes(keccak256(&build_node(&entries, 0, None, &mut vec![])))
+    }
+
+    /// returns the RLP encoded trie nodes on the path of `key` from the root, like `accountProof` and `storageProof` of `eth_getProof`
+    ///
+    /// if the key does not exist, the nodes prove its absence
+    pub fn proof(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let entries = self.sorted_entries();
+        let target = to_nibbles(&keccak256(key));
+        let mut nodes = vec![];
+        let root = build_node(&entries, 0, Some(&target), &mut nodes);
+        if root.len() < 32 {
+            // the root node is always referenced by its hash
+            nodes.push(root);
+        }
+        nodes.reverse();
+        nodes
+    }
+
+    fn sorted_entries(&self) -> Vec<(&[u8], &[u8])> {
+        self.entries
+            .iter()
+            .map(|(k, v)| (k.as_slice(), v.as_slice()))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MockAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code_hash: H256,
+    pub storage: MemoryTrie,
+}
+
+impl Default for MockAccount {
+    fn default() -> Self {
+        Self {
+            nonce: 0,
+            balance: U256::ZERO,
+            code_hash: H256::from_be_bytes(EMPTY_CODE_HASH),
+            storage: MemoryTrie::new(),
+        }
+    }
+}
+
+impl MockAccount {
+    /// sets the storage slot to `value`, which is stored as the RLP encoded value without leading zeros
+    ///
+    /// the slot is removed if `value` is zero
+    pub fn set_storage(&mut self, slot: U256, value: U256) {
+        let key: [u8; 32] = slot.to_be_bytes();
+        if value.is_zero() {
+            self.storage.remove(&key);
+        } else {
+            let value: [u8; 32] = value.to_be_bytes();
+            let pos = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+            self.storage
+                .insert(&key, rlp::encode(&value[pos..].to_vec()).to_vec());
+        }
+    }
+
+    /// sets the commitment of the IBC store for `path` as `IBCStore` does
+    pub fn set_ibc_commitment(&mut self, path: &str, value: &[u8]) {
+        let key: [u8; 32] = calculate_ibc_commitment_storage_key(path.as_bytes()).to_be_bytes();
+        self.storage
+            .insert(&key, calculate_ibc_commitment_storage_value(value));
+    }
+
+    pub fn remove_ibc_commitment(&mut self, path: &str) {
+        let key: [u8; 32] = calculate_ibc_commitment_storage_key(path.as_bytes()).to_be_bytes();
+        self.storage.remove(&key);
+    }
+
+    pub fn storage_root(&self) -> H256 {
+        self.storage.root()
+    }
+
+    /// returns the RLP encoded account: `[nonce, balance, storage_root, code_hash]`
+    pub fn rlp_bytes(&self) -> Vec<u8> {
+        let balance: [u8; 32] = self.balance.to_be_bytes();
+        let pos = balance
+            .iter()
+            .position(|b| *b != 0)
+            .unwrap_or(balance.len());
+        let mut stream = RlpStream::new_list(4);
+        stream.append(&self.nonce);
+        stream.append(&balance[pos..].to_vec());
+        stream.append(&self.storage_root().to_be_bytes::<32>().to_vec());
+        stream.append(&self.code_hash.to_be_bytes::<32>().to_vec());
+        stream.out().to_vec()
+    }
+}
+
+/// world state consisting of accounts and their storages
+#[derive(Clone, Debug, Default)]
+pub struct MockStateTrie {
+    pub accounts: BTreeMap<Address, MockAccount>,
+}
+
+impl MockStateTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns the account of `address`, which is created if it does not exist
+    pub fn account_mut(&mut self, address: Address) -> &mut MockAccount {
+        self.accounts.entry(address).or_default()
+    }
+
+    pub fn set_ibc_commitment(&mut self, address: Address, path: &str, value: &[u8]) {
+        self.account_mut(address).set_ibc_commitment(path, value);
+    }
+
+    pub fn remove_ibc_commitment(&mut self, address: Address, path: &str) {
+        self.account_mut(address).remove_ibc_commitment(path);
+    }
+
+    pub fn state_root(&self) -> H256 {
+        self.state_trie().root()
+    }
+
+    /// returns the storage root of the account, or the empty trie root if the account does not exist
+    pub fn storage_root(&self, address: &Address) -> H256 {
+        self.accounts
+            .get(address)
+            .map(|account| account.storage_root())
+            .unwrap_or_else(|| MemoryTrie::new().root())
+    }
+
+    /// returns the account proof of `address` in the format of `decode_eip1184_rlp_proof`
+    pub fn account_proof(&self, address: &Address) -> Vec<u8> {
+        encode_proof(&self.state_trie().proof(address))
+    }
+
+    /// returns the storage proof of the IBC commitment for `path` in the format of `decode_eip1184_rlp_proof`
+    pub fn ibc_commitment_proof(&self, address: &Address, path: &str) -> Vec<u8> {
+        let key: [u8; 32] = calculate_ibc_commitment_storage_key(path.as_bytes()).to_be_bytes();
+        self.storage_proof(address, &key)
+    }
+
+    /// returns the storage proof of the IBC commitments for `paths` in the multiproof format, which is the proof of `verify_memberships`
+    pub fn ibc_commitment_multiproof(&self, address: &Address, paths: &[&str]) -> Vec<u8> {
+        let storage = self
+            .accounts
+            .get(address)
+            .map(|account| account.storage.clone())
+            .unwrap_or_default();
+        let proofs: Vec<Vec<Vec<u8>>> = paths
+            .iter()
+            .map(|path| {
+                let key: [u8; 32] =
+                    calculate_ibc_commitment_storage_key(path.as_bytes()).to_be_bytes();
+                storage.proof(&key)
+            })
+            .collect();
+        encode_eip1184_rlp_multiproof(&proofs)
+    }
+
+    /// returns the storage proof of the slot `key` in the format of `decode_eip1184_rlp_proof`
+    pub fn storage_proof(&self, address: &Address, key: &[u8]) -> Vec<u8> {
+        let storage = self
+            .accounts
+            .get(address)
+            .map(|account| account.storage.clone())
+            .unwrap_or_default();
+        encode_proof(&storage.proof(key))
+    }
+
+    fn state_trie(&self) -> MemoryTrie {
+        let mut trie = MemoryTrie::new();
+        for (address, account) in self.accounts.iter() {
+            trie.insert(address, account.rlp_bytes());
+        }
+        trie
+    }
+}
+
+/// encodes the RLP encoded trie nodes as `List<List>`, which is the format of the proofs in `Header` and `verify_membership`
+pub fn encode_proof(nodes: &[Vec<u8>]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(nodes.len());
+    for node in nodes {
+        stream.append_raw(node, 1);
+    }
+    stream.out().to_vec()
+}
+
+fn to_nibbles(bz: &[u8]) -> Vec<u8> {
+    bz.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// hex prefix encoding of the nibbles in the leaf and extension nodes
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let mut bz = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        bz.push(((flag + 1) << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        bz.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        bz.push((pair[0] << 4) | pair[1]);
+    }
+    bz
+}
+
+/// appends the reference to the child node, which is embedded if its encoding is shorter than 32 bytes
+fn append_child(stream: &mut RlpStream, node: &[u8]) {
+    if node.len() < 32 {
+        stream.append_raw(node, 1);
+    } else {
+        stream.append(&keccak256(node).to_vec());
+    }
+}
+
+/// builds the RLP encoded node for `entries` whose keys share the first `depth` nibbles
+///
+/// the nodes on the path of `target`, which are referenced by their hashes, are pushed to `proof` from the leaf side
+fn build_node(
+    entries: &[(&[u8], &[u8])],
+    depth: usize,
+    target: Option<&[u8]>,
+    proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let node = match entries {
+        [] => rlp::NULL_RLP.to_vec(),
+        [(key, value)] => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&encode_path(&key[depth..], true));
+            stream.append(&value.to_vec());
+            stream.out().to_vec()
+        }
+        _ => {
+            let first = entries[0].0;
+            let last = entries[entries.len() - 1].0;
+            // the entries are sorted, so the common prefix of all keys is the one of the first and last keys
+            let prefix_len = first[depth..]
+                .iter()
+                .zip(last[depth..].iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if prefix_len > 0 {
+                let target = target
+                    .filter(|t| t[depth..depth + prefix_len] == first[depth..depth + prefix_len]);
+                let child = build_node(entries, depth + prefix_len, target, proof);
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&encode_path(&first[depth..depth + prefix_len], false));
+                append_child(&mut stream, &child);
+                stream.out().to_vec()
+            } else {
+                let mut stream = RlpStream::new_list(17);
+                for nibble in 0..16u8 {
+                    let start = entries.partition_point(|(k, _)| k[depth] < nibble);
+                    let end = entries.partition_point(|(k, _)| k[depth] <= nibble);
+                    if start == end {
+                        stream.append_empty_data();
+                    } else {
+                        let target = target.filter(|t| t[depth] == nibble);
+                        let child = build_node(&entries[start..end], depth + 1, target, proof);
+                        append_child(&mut stream, &child);
+                    }
+                }
+                // the keys have the same length, so the branch node never has a value
+                stream.append_empty_data();
+                stream.out().to_vec()
+            }
+        }
+    };
+    if target.is_some() && node.len() >= 32 {
+        proof.push(node.clone());
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_state::ClientState;
+    use crate::commitment::ProofNodes;
+    use crate::errors::Error;
+    use alloc::format;
+
+    #[test]
+    fn test_empty_trie() {
+        let trie = MemoryTrie::new();
+        assert_eq!(
+            trie.root(),
+            H256::from_be_bytes(hex!(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_ibc_commitment_proofs() {
+        let address = hex!("702e40245797c5a2108a566b3ce2bf14bc6af841");
+        let mut state = MockStateTrie::new();
+        state
+            .account_mut(hex!("a5c8416b9d13417b45b45ada76408f39d1e504ef"))
+            .balance = U256::from(1000u64);
+        for i in 0..16 {
+            state.set_ibc_commitment(address, &format!("commitments/{}", i), &[i as u8]);
+        }
+        let state_root = state.state_root();
+        let client_state = ClientState::default();
+
+        let storage_root = client_state
+            .verify_account_storage(state.account_proof(&address), state_root, &address)
+            .unwrap();
+        assert_eq!(storage_root, state.storage_root(&address));
+
+        for i in 0..16 {
+            let path = format!("commitments/{}", i);
+            let proof = state.ibc_commitment_proof(&address, &path);
+            client_state
+                .verify_membership(proof.clone(), storage_root, path.clone(), vec![i as u8])
+                .unwrap();
+            assert!(client_state
+                .verify_membership(proof, storage_root, path, vec![i as u8 + 1])
+                .is_err());
+        }
+
+        let path = "commitments/16".to_string();
+        let proof = state.ibc_commitment_proof(&address, &path);
+        client_state
+            .verify_non_membership(proof.clone(), storage_root, path.clone())
+            .unwrap();
+        assert!(client_state
+            .verify_membership(proof, storage_root, path, vec![16])
+            .is_err());
+
+        // the account does not exist
+        let unknown = hex!("0000000000000000000000000000000000000001");
+        assert!(client_state
+            .verify_account_storage(state.account_proof(&unknown), state_root, &unknown)
+            .is_err());
+    }
+
+    #[test]
+    fn test_proof_nodes() {
+        let mut trie = MemoryTrie::new();
+        for i in 0..64u64 {
+            trie.insert(&i.to_be_bytes(), vec![i as u8 + 1; 1 + i as usize % 40]);
+        }
+        let root = trie.root();
+        let keys: Vec<[u8; 8]> = (0..80u64).map(u64::to_be_bytes).collect();
+        let proofs: Vec<Vec<Vec<u8>>> = keys.iter().map(|key| trie.proof(key)).collect();
+        let nodes = ProofNodes::decode(&encode_eip1184_rlp_multiproof(&proofs)).unwrap();
+        // the present and absent keys are looked up against the same nodes
+        for key in keys.iter() {
+            assert_eq!(
+                nodes.get(root, key).unwrap(),
+                trie.get(key).map(Vec::as_slice)
+            );
+        }
+
+        // the nodes do not cover the path of the key
+        let nodes = ProofNodes::decode(&encode_proof(&trie.proof(&keys[0]))).unwrap();
+        assert!(matches!(
+            nodes.get(root, &keys[1]),
+            Err(Error::TrieNodeNotFound(_))
+        ));
+        // the root does not match
+        assert!(matches!(
+            nodes.get(H256::from_be_bytes([1u8; 32]), &keys[0]),
+            Err(Error::TrieNodeNotFound(_))
+        ));
+        // nothing is in the empty trie
+        assert_eq!(
+            ProofNodes::default()
+                .get(MemoryTrie::new().root(), &keys[0])
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_memberships() {
+        let address = hex!("702e40245797c5a2108a566b3ce2bf14bc6af841");
+        let mut state = MockStateTrie::new();
+        for i in 0..16 {
+            state.set_ibc_commitment(address, &format!("commitments/{}", i), &[i as u8]);
+        }
+        let storage_root = state.storage_root(&address);
+        let client_state = ClientState::default();
+        let paths = ["commitments/0", "commitments/7", "commitments/16"];
+        let proof = state.ibc_commitment_multiproof(&address, &paths);
+        let items = |values: [Option<u8>; 3]| -> Vec<(String, Option<Vec<u8>>)> {
+            paths
+                .iter()
+                .zip(values)
+                .map(|(path, value)| (path.to_string(), value.map(|v| vec![v])))
+                .collect()
+        };
+
+        client_state
+            .verify_memberships(
+                proof.clone(),
+                storage_root,
+                &items([Some(0), Some(7), None]),
+            )
+            .unwrap();
+        // a wrong value of a single item fails the whole batch
+        assert!(matches!(
+            client_state.verify_memberships(
+                proof.clone(),
+                storage_root,
+                &items([Some(0), Some(8), None])
+            ),
+            Err(Error::MembershipValueMismatch { path, .. }) if path == "commitments/7"
+        ));
+        // an existing commitment cannot be verified as a non-membership
+        assert!(matches!(
+            client_state.verify_memberships(
+                proof.clone(),
+                storage_root,
+                &items([None, Some(7), None])
+            ),
+            Err(Error::MembershipValueMismatch { path, .. }) if path == "commitments/0"
+        ));
+        // an absent commitment cannot be verified as a membership
+        assert!(matches!(
+            client_state.verify_memberships(
+                proof,
+                storage_root,
+                &items([Some(0), Some(7), Some(16)])
+            ),
+            Err(Error::MembershipValueMismatch { path, .. }) if path == "commitments/16"
+        ));
+        // the proof does not cover all the items
+        let proof = state.ibc_commitment_multiproof(&address, &paths[..1]);
+        assert!(client_state
+            .verify_memberships(proof, storage_root, &items([Some(0), Some(7), None]))
+            .is_err());
+    }
+}
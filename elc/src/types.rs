@@ -1,6 +1,6 @@
 use crate::{
     client_state::{canonicalize_client_state, ClientState},
-    consensus_state::ConsensusState,
+    consensus_state::{canonicalize_consensus_state, ConsensusState},
     errors::Error,
 };
 use light_client::commitments::{gen_state_id_from_any, StateID};
@@ -17,6 +17,6 @@ pub fn gen_state_id(
 ) -> Result<StateID, Error> {
     Ok(gen_state_id_from_any(
         &canonicalize_client_state(client_state).into(),
-        &consensus_state.into(),
+        &canonicalize_consensus_state(consensus_state).try_into()?,
     )?)
 }
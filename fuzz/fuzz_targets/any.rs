@@ -0,0 +1,36 @@
+#![no_main]
+use besu_qbft_elc::client_state::{ClientState, BESU_QBFT_CLIENT_STATE_TYPE_URL};
+use besu_qbft_elc::consensus_state::{ConsensusState, BESU_QBFT_CONSENSUS_STATE_TYPE_URL};
+use besu_qbft_elc::message::{
+    Checkpoint, ClientMessage, BESU_QBFT_CHECKPOINT_TYPE_URL, BESU_QBFT_HEADER_TYPE_URL,
+    BESU_QBFT_REVISION_UPGRADE_TYPE_URL,
+};
+use libfuzzer_sys::fuzz_target;
+use light_client::types::Any;
+
+const TYPE_URLS: [&str; 5] = [
+    BESU_QBFT_CLIENT_STATE_TYPE_URL,
+    BESU_QBFT_CONSENSUS_STATE_TYPE_URL,
+    BESU_QBFT_HEADER_TYPE_URL,
+    BESU_QBFT_REVISION_UPGRADE_TYPE_URL,
+    BESU_QBFT_CHECKPOINT_TYPE_URL,
+];
+
+// the first byte selects the type url, and the rest is the protobuf encoded value
+fuzz_target!(|data: &[u8]| {
+    let Some((selector, value)) = data.split_first() else {
+        return;
+    };
+    let any = Any::new(
+        TYPE_URLS[*selector as usize % TYPE_URLS.len()].to_string(),
+        value.to_vec(),
+    );
+    if let Ok(client_state) = ClientState::try_from(any.clone()) {
+        let _ = client_state.validate();
+    }
+    if let Ok(consensus_state) = ConsensusState::try_from(any.clone()) {
+        let _ = consensus_state.validate();
+    }
+    let _ = ClientMessage::try_from(any.clone());
+    let _ = Checkpoint::try_from(any);
+});
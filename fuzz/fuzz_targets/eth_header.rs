@@ -0,0 +1,9 @@
+#![no_main]
+use besu_qbft_elc::header::EthHeader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = EthHeader::parse(data) {
+        let _ = header.commit_hash();
+    }
+});
@@ -0,0 +1,7 @@
+#![no_main]
+use besu_qbft_elc::header::QbftExtra;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = QbftExtra::decode(data);
+});
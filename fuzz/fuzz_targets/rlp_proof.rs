@@ -0,0 +1,7 @@
+#![no_main]
+use besu_qbft_elc::commitment::decode_eip1184_rlp_proof;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_eip1184_rlp_proof(data);
+});
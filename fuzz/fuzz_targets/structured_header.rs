@@ -0,0 +1,46 @@
+#![no_main]
+use besu_qbft_elc::header::EthHeader;
+use besu_qbft_elc::test_utils::qbft::{encode_extra, encode_header};
+use besu_qbft_elc::types::{H256, U256};
+use libfuzzer_sys::arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    number: u64,
+    timestamp: u64,
+    state_root: [u8; 32],
+    round: u32,
+    validators: Vec<[u8; 20]>,
+    seals: Vec<Vec<u8>>,
+    /// `(position, value)` pairs to overwrite the bytes of the encoded header
+    mutations: Vec<(u16, u8)>,
+}
+
+// generates well-formed headers, which are optionally corrupted, to reach deeper paths than random bytes
+fuzz_target!(|input: Input| {
+    let extra = encode_extra(&input.validators, input.round, &input.seals);
+    let mut header_rlp = encode_header(
+        input.number,
+        input.timestamp,
+        H256::from_be_bytes(input.state_root),
+        &extra,
+    );
+    if input.mutations.is_empty() {
+        let header = EthHeader::parse(&header_rlp).unwrap();
+        assert_eq!(header.number, U256::from(input.number));
+        assert_eq!(header.timestamp, U256::from(input.timestamp));
+        assert_eq!(header.extra.round, input.round);
+        assert_eq!(header.extra.validators, input.validators);
+        assert_eq!(header.extra.committed_seals, input.seals);
+        assert_eq!(header.commit_hash().is_ok(), input.seals.is_empty());
+    } else {
+        for (pos, value) in input.mutations {
+            let len = header_rlp.len();
+            header_rlp[pos as usize % len] = value;
+        }
+        if let Ok(header) = EthHeader::parse(&header_rlp) {
+            let _ = header.commit_hash();
+        }
+    }
+});
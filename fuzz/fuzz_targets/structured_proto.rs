@@ -0,0 +1,173 @@
+#![no_main]
+use besu_qbft_elc::client_state::{ClientState, BESU_QBFT_CLIENT_STATE_TYPE_URL};
+use besu_qbft_elc::consensus_state::{ConsensusState, BESU_QBFT_CONSENSUS_STATE_TYPE_URL};
+use besu_qbft_elc::message::{
+    Checkpoint, ClientMessage, BESU_QBFT_CHECKPOINT_TYPE_URL, BESU_QBFT_HEADER_TYPE_URL,
+    BESU_QBFT_REVISION_UPGRADE_TYPE_URL,
+};
+use besu_qbft_elc::types::gen_state_id;
+use besu_qbft_proto::ibc::{
+    core::client::v1::Height as RawHeight,
+    lightclients::qbft::v1::{
+        Checkpoint as RawCheckpoint, ClientState as RawClientState,
+        ConsensusState as RawConsensusState, Header as RawHeader,
+        RevisionUpgrade as RawRevisionUpgrade,
+    },
+};
+use libfuzzer_sys::arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use light_client::types::Any;
+use prost::Message;
+
+fn any(type_url: &str, message: impl Message) -> Any {
+    Any::new(type_url.to_string(), message.encode_to_vec())
+}
+
+#[derive(Arbitrary, Debug)]
+struct InputHeight {
+    revision_number: u64,
+    revision_height: u64,
+}
+
+impl From<InputHeight> for RawHeight {
+    fn from(value: InputHeight) -> Self {
+        RawHeight {
+            revision_number: value.revision_number,
+            revision_height: value.revision_height,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct InputClientState {
+    chain_id: Vec<u8>,
+    ibc_store_address: Vec<u8>,
+    latest_height: Option<InputHeight>,
+    trusting_period: u64,
+    max_clock_drift: u64,
+    use_validators_hash: bool,
+    allow_backfill: bool,
+    frozen_height: Option<InputHeight>,
+    timestamp_precision: i32,
+    trusting_period_nanos: u32,
+    max_clock_drift_nanos: u32,
+    emit_client_state: bool,
+    delay_time_period: u64,
+    delay_time_period_nanos: u32,
+    revision_schedule: Vec<InputHeight>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct InputConsensusState {
+    timestamp: u64,
+    root: Vec<u8>,
+    validators: Vec<Vec<u8>>,
+    validators_hash: Vec<u8>,
+    timestamp_nanos: u32,
+    processed_time: u64,
+}
+
+#[derive(Arbitrary, Debug)]
+struct InputHeader {
+    besu_header_rlp: Vec<u8>,
+    seals: Vec<Vec<u8>>,
+    trusted_height: Option<InputHeight>,
+    account_state_proof: Vec<u8>,
+    trusted_validators: Vec<Vec<u8>>,
+}
+
+impl From<InputHeader> for RawHeader {
+    fn from(value: InputHeader) -> Self {
+        RawHeader {
+            besu_header_rlp: value.besu_header_rlp,
+            seals: value.seals,
+            trusted_height: value.trusted_height.map(Into::into),
+            account_state_proof: value.account_state_proof,
+            trusted_validators: value.trusted_validators,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Input {
+    States(InputClientState, InputConsensusState),
+    Header(InputHeader),
+    RevisionUpgrade(Option<InputHeader>),
+    Checkpoint {
+        block_hash: Vec<u8>,
+        besu_header_rlp: Vec<u8>,
+        account_state_proof: Vec<u8>,
+    },
+}
+
+// generates protobuf messages with arbitrary field values, which are always decodable by prost
+fuzz_target!(|input: Input| {
+    match input {
+        Input::States(client_state, consensus_state) => {
+            let client_state = RawClientState {
+                chain_id: client_state.chain_id,
+                ibc_store_address: client_state.ibc_store_address,
+                latest_height: client_state.latest_height.map(Into::into),
+                trusting_period: client_state.trusting_period,
+                max_clock_drift: client_state.max_clock_drift,
+                use_validators_hash: client_state.use_validators_hash,
+                allow_backfill: client_state.allow_backfill,
+                frozen_height: client_state.frozen_height.map(Into::into),
+                timestamp_precision: client_state.timestamp_precision,
+                trusting_period_nanos: client_state.trusting_period_nanos,
+                max_clock_drift_nanos: client_state.max_clock_drift_nanos,
+                emit_client_state: client_state.emit_client_state,
+                delay_time_period: client_state.delay_time_period,
+                delay_time_period_nanos: client_state.delay_time_period_nanos,
+                revision_schedule: client_state
+                    .revision_schedule
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            };
+            let consensus_state = RawConsensusState {
+                timestamp: consensus_state.timestamp,
+                root: consensus_state.root,
+                validators: consensus_state.validators,
+                validators_hash: consensus_state.validators_hash,
+                timestamp_nanos: consensus_state.timestamp_nanos,
+                processed_time: consensus_state.processed_time,
+            };
+            let client_state =
+                ClientState::try_from(any(BESU_QBFT_CLIENT_STATE_TYPE_URL, client_state));
+            let consensus_state =
+                ConsensusState::try_from(any(BESU_QBFT_CONSENSUS_STATE_TYPE_URL, consensus_state));
+            if let (Ok(client_state), Ok(consensus_state)) = (client_state, consensus_state) {
+                let _ = client_state.validate();
+                let _ = consensus_state.validate();
+                let _ = gen_state_id(client_state, consensus_state);
+            }
+        }
+        Input::Header(header) => {
+            let _ =
+                ClientMessage::try_from(any(BESU_QBFT_HEADER_TYPE_URL, RawHeader::from(header)));
+        }
+        Input::RevisionUpgrade(header) => {
+            let _ = ClientMessage::try_from(any(
+                BESU_QBFT_REVISION_UPGRADE_TYPE_URL,
+                RawRevisionUpgrade {
+                    header: header.map(Into::into),
+                },
+            ));
+        }
+        Input::Checkpoint {
+            block_hash,
+            besu_header_rlp,
+            account_state_proof,
+        } => {
+            let _ = Checkpoint::try_from(any(
+                BESU_QBFT_CHECKPOINT_TYPE_URL,
+                RawCheckpoint {
+                    block_hash,
+                    besu_header_rlp,
+                    account_state_proof,
+                },
+            ));
+        }
+    }
+});
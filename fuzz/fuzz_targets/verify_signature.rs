@@ -0,0 +1,13 @@
+#![no_main]
+use besu_qbft_elc::commitment::verify_signature;
+use besu_qbft_elc::types::H256;
+use libfuzzer_sys::fuzz_target;
+
+// the first 32 bytes are the signed hash, and the rest is the seal
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 32 {
+        return;
+    }
+    let (hash, seal) = data.split_at(32);
+    let _ = verify_signature(H256::try_from_be_slice(hash).unwrap(), seal);
+});
@@ -9,23 +9,95 @@ pub struct ClientState {
     pub latest_height: ::core::option::Option<
         super::super::super::core::client::v1::Height,
     >,
-    /// duration in seconds
-    /// if this is set to 0, the client will not verify the header's timestamp is within the trusting period
+    /// seconds part of the duration
+    /// if this and `trusting_period_nanos` are set to 0, the client will not verify the header's timestamp is within the trusting period
     #[prost(uint64, tag = "4")]
     pub trusting_period: u64,
-    /// duration in seconds
+    /// seconds part of the duration
     #[prost(uint64, tag = "5")]
     pub max_clock_drift: u64,
+    /// if this is set to true, the client stores `validators_hash` instead of `validators` in the consensus states created by updates
+    #[prost(bool, tag = "6")]
+    pub use_validators_hash: bool,
+    /// if this is set to true, the client accepts headers whose height is not greater than the latest height
+    /// such headers are verified against the trusted consensus state but do not advance the latest height
+    /// NOTE: the clients created before this field was introduced accepted such headers without it,
+    /// so they must be re-created with this set to true to keep backfilling the consensus states
+    #[prost(bool, tag = "7")]
+    pub allow_backfill: bool,
+    /// height at which the client was frozen due to a misbehaviour
+    /// if this is not set, the client is not frozen
+    #[prost(message, optional, tag = "8")]
+    pub frozen_height: ::core::option::Option<
+        super::super::super::core::client::v1::Height,
+    >,
+    /// precision of the timestamp in the Besu header
+    #[prost(enumeration = "TimestampPrecision", tag = "9")]
+    pub timestamp_precision: i32,
+    /// nanoseconds part of `trusting_period`, which must be less than 1e9
+    #[prost(uint32, tag = "10")]
+    pub trusting_period_nanos: u32,
+    /// nanoseconds part of `max_clock_drift`, which must be less than 1e9
+    #[prost(uint32, tag = "11")]
+    pub max_clock_drift_nanos: u32,
+    /// if this is set to true, the client emits the new client state at the post height in the messages of updates
+    #[prost(bool, tag = "12")]
+    pub emit_client_state: bool,
+    /// seconds part of the delay period that must pass after a consensus state is processed before it can be used for membership verification
+    /// the host height is not available to the light client, so only the time delay is supported
+    /// the LCP host cannot give the delay period of the connection in ICS-07 to the verification, so this must be set to it
+    #[prost(uint64, tag = "13")]
+    pub delay_time_period: u64,
+    /// nanoseconds part of `delay_time_period`, which must be less than 1e9
+    #[prost(uint32, tag = "14")]
+    pub delay_time_period_nanos: u32,
+    /// first heights of the revisions after the revision of the initial latest height, in ascending order of the revision number
+    /// `RevisionUpgrade` is only accepted for a header at the first height of the next revision
+    #[prost(message, repeated, tag = "15")]
+    pub revision_schedule: ::prost::alloc::vec::Vec<
+        super::super::super::core::client::v1::Height,
+    >,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ConsensusState {
+    /// unix timestamp in seconds
     #[prost(uint64, tag = "1")]
     pub timestamp: u64,
     #[prost(bytes = "vec", tag = "2")]
     pub root: ::prost::alloc::vec::Vec<u8>,
+    /// validator addresses of the block
+    /// this field must be empty if `validators_hash` is set
     #[prost(bytes = "vec", repeated, tag = "3")]
     pub validators: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// keccak256 hash of the concatenation of the validator addresses sorted in ascending order
+    /// if this is set, the header that refers to this consensus state must contain the validators as `trusted_validators`
+    #[prost(bytes = "vec", tag = "4")]
+    pub validators_hash: ::prost::alloc::vec::Vec<u8>,
+    /// sub-second part of the timestamp in nanoseconds
+    #[prost(uint32, tag = "5")]
+    pub timestamp_nanos: u32,
+    /// unix timestamp in nanoseconds of the host at which the consensus state was processed
+    /// this is not included in the state ID
+    /// if this is not set, the consensus state cannot be used for the verification with a delay period
+    /// the initial consensus state must have this within `max_clock_drift` from the host timestamp if it is set
+    #[prost(uint64, tag = "6")]
+    pub processed_time: u64,
+}
+/// Checkpoint can be given to `create_client` instead of ConsensusState to create a client from a trusted block hash
+/// the initial consensus state is derived from the header and the account proof in the enclave
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Checkpoint {
+    /// hash of the checkpoint block, which must be equal to the keccak256 hash of `besu_header_rlp`
+    #[prost(bytes = "vec", tag = "2")]
+    pub block_hash: ::prost::alloc::vec::Vec<u8>,
+    /// RLP encoded header of Besu at `latest_height` of the client state as stored in the chain, which includes the seals in the extra data
+    #[prost(bytes = "vec", tag = "3")]
+    pub besu_header_rlp: ::prost::alloc::vec::Vec<u8>,
+    /// proof of the account of `ibc_store_address` in the state of the header
+    #[prost(bytes = "vec", tag = "4")]
+    pub account_state_proof: ::prost::alloc::vec::Vec<u8>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -41,4 +113,48 @@ pub struct Header {
     >,
     #[prost(bytes = "vec", tag = "4")]
     pub account_state_proof: ::prost::alloc::vec::Vec<u8>,
+    /// validators of the trusted consensus state
+    /// this field is required only if the trusted consensus state contains `validators_hash` instead of `validators`
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub trusted_validators: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+/// RevisionUpgrade moves the client to the next revision
+/// it is used when the network is restarted from a new genesis or hard forked with a reset of the block number
+/// the header is verified against the trusted consensus state in the same way as `Header`,
+/// and the new height is `{latest_height.revision_number + 1}-{block number of the header}`,
+/// which must be scheduled in `revision_schedule` of the client state
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevisionUpgrade {
+    /// `trusted_height` of the header must belong to the revision of the latest height
+    #[prost(message, optional, tag = "1")]
+    pub header: ::core::option::Option<Header>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TimestampPrecision {
+    /// the timestamp is in seconds, which is the default of Ethereum and Besu
+    Seconds = 0,
+    /// the timestamp is in milliseconds, which is used by networks with sub-second block periods
+    Milliseconds = 1,
+}
+impl TimestampPrecision {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            TimestampPrecision::Seconds => "TIMESTAMP_PRECISION_SECONDS",
+            TimestampPrecision::Milliseconds => "TIMESTAMP_PRECISION_MILLISECONDS",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TIMESTAMP_PRECISION_SECONDS" => Some(Self::Seconds),
+            "TIMESTAMP_PRECISION_MILLISECONDS" => Some(Self::Milliseconds),
+            _ => None,
+        }
+    }
 }